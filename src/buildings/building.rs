@@ -1,8 +1,23 @@
 use crate::buildings::building_menu::BuildingListGizmo;
+use crate::buildings::construction::{spawn_construction_site, ConstructionSite};
 use crate::buildings::OpenForBuilding;
 use crate::camera_control::MouseTargetedEntity;
+use crate::buildings::placement::{
+    footprint_tiles, is_valid_placement, placement_rotation, BuildingFootprint,
+};
+use crate::errands::Demolishable;
+use crate::game_level::{GameLevel, ResourceType, HALF_TILE_SIZE, TILE_SIZE};
+use crate::health::Health;
+use crate::grid::GridPosition;
 use crate::prelude::*;
+use crate::stockpile::Stockpile;
+use bevy_ecs::system::EntityCommands;
+use oxidized_navigation::NavMeshAffector;
+use std::collections::HashMap;
 use std::ops::Deref;
+use std::sync::Arc;
+
+const BUILDING_HALF_HEIGHT: f32 = 2.5;
 
 pub trait Building: Clone + Send + Sync + 'static {
     type Assets: Resource + 'static;
@@ -11,19 +26,54 @@ pub trait Building: Clone + Send + Sync + 'static {
     fn get_name() -> String;
     fn get_order() -> i32;
     fn get_icon(&self) -> Handle<Image>;
+    fn get_cost() -> Vec<(ResourceType, u32)>;
+    /// Tiles covered by the building, relative to the tile it is placed on.
+    fn get_footprint() -> Vec<GridPosition> {
+        vec![GridPosition::new(0, 0)]
+    }
+    /// Seconds of work needed once all materials are delivered.
+    fn get_build_time() -> f32;
     fn initialize(assets: &Self::Assets) -> Self;
+
+    /// Adds the components specific to this building once construction is finished.
+    fn on_completed(&self, _building: &mut EntityCommands) {}
 }
 
 pub trait BuildingInfo: Send + Sync + 'static {
     fn get_model(&self) -> Handle<Scene>;
+    fn get_name(&self) -> String;
+    fn get_cost(&self) -> Vec<(ResourceType, u32)>;
+    fn get_footprint(&self) -> Vec<GridPosition>;
+    fn get_build_time(&self) -> f32;
+    fn on_completed(&self, building: &mut EntityCommands);
 }
 
-struct BuildingInfoWrapper<B: Building>(B);
+pub struct BuildingInfoWrapper<B: Building>(pub B);
 
 impl<B: Building> BuildingInfo for BuildingInfoWrapper<B> {
     fn get_model(&self) -> Handle<Scene> {
         self.0.get_model()
     }
+
+    fn get_name(&self) -> String {
+        B::get_name()
+    }
+
+    fn get_cost(&self) -> Vec<(ResourceType, u32)> {
+        B::get_cost()
+    }
+
+    fn get_footprint(&self) -> Vec<GridPosition> {
+        B::get_footprint()
+    }
+
+    fn get_build_time(&self) -> f32 {
+        B::get_build_time()
+    }
+
+    fn on_completed(&self, building: &mut EntityCommands) {
+        self.0.on_completed(building)
+    }
 }
 
 #[derive(Resource)]
@@ -61,11 +111,12 @@ impl<B: Building> Gizmo for BuildingGizmo<B> {
 
 #[derive(Resource)]
 pub struct PlacingBuilding {
-    info: Box<dyn BuildingInfo>,
+    info: Arc<dyn BuildingInfo>,
+    quarter_turns: u8,
 }
 
 impl Deref for PlacingBuilding {
-    type Target = Box<dyn BuildingInfo>;
+    type Target = Arc<dyn BuildingInfo>;
 
     fn deref(&self) -> &Self::Target {
         &self.info
@@ -80,12 +131,36 @@ fn start_building_when_gizmo_clicked<B: Building>(
     for interaction in q.iter() {
         if *interaction == Interaction::Pressed {
             commands.insert_resource(PlacingBuilding {
-                info: Box::new(BuildingInfoWrapper::<B>(gizmo.building.clone())),
+                info: Arc::new(BuildingInfoWrapper::<B>(gizmo.building.clone())),
+                quarter_turns: 0,
             });
         }
     }
 }
 
+/// Every building type that has been added to the app, by name, so saved buildings can be
+/// rebuilt.
+#[derive(Resource, Default)]
+pub struct BuildingTypes {
+    by_name: HashMap<String, Arc<dyn BuildingInfo>>,
+}
+
+impl BuildingTypes {
+    pub fn get(&self, name: &str) -> Option<Arc<dyn BuildingInfo>> {
+        self.by_name.get(name).cloned()
+    }
+}
+
+fn register_building_type<B: Building>(
+    mut types: ResMut<BuildingTypes>,
+    gizmo: Res<BuildingGizmo<B>>,
+) {
+    types.by_name.insert(
+        B::get_name(),
+        Arc::new(BuildingInfoWrapper::<B>(gizmo.building.clone())),
+    );
+}
+
 pub trait BuildingAppExtensions {
     fn add_building<B: Building>(&mut self) -> &mut Self;
 }
@@ -94,10 +169,15 @@ impl BuildingAppExtensions for App {
     fn add_building<B: Building>(&mut self) -> &mut Self {
         add_base_gizmo_systems::<BuildingGizmo<B>>(self);
 
-        self.add_systems(
-            Update,
-            start_building_when_gizmo_clicked::<B>.run_if(resource_exists::<BuildingGizmo<B>>()),
-        )
+        self.init_resource::<BuildingTypes>()
+            .add_systems(
+                Update,
+                (
+                    start_building_when_gizmo_clicked::<B>
+                        .run_if(resource_exists::<BuildingGizmo<B>>()),
+                    register_building_type::<B>.run_if(resource_added::<BuildingGizmo<B>>()),
+                ),
+            )
     }
 }
 
@@ -106,64 +186,102 @@ pub fn is_placing_building(b: Option<Res<PlacingBuilding>>) -> bool {
 }
 
 #[derive(Component)]
-pub struct BuildingPlaceholder;
+pub struct BuildingPlaceholder {
+    tiles: Vec<GridPosition>,
+    is_valid: bool,
+}
+
+pub fn rotate_building(
+    control: Query<&ActionState<ControlAction>>,
+    mut placing: ResMut<PlacingBuilding>,
+) {
+    for action_state in control.iter() {
+        if action_state.just_pressed(ControlAction::RotateBuildingLeft) {
+            placing.quarter_turns = (placing.quarter_turns + 1) % 4;
+        }
+
+        if action_state.just_pressed(ControlAction::RotateBuildingRight) {
+            placing.quarter_turns = (placing.quarter_turns + 3) % 4;
+        }
+    }
+}
 
 pub fn place_building(
     mut commands: Commands,
-    mut placeholder: Query<(Entity, &mut Transform), With<BuildingPlaceholder>>,
+    mut placeholder: Query<(Entity, &mut Transform, &mut BuildingPlaceholder)>,
     info: Res<PlacingBuilding>,
     mouse_target: Res<MouseTargetedEntity>,
     floor_query: Query<&GlobalTransform, With<OpenForBuilding>>,
+    footprints: Query<&BuildingFootprint>,
+    level: Res<GameLevel>,
 ) {
-    let placeholder_translation = if let Some(mouse_target) = &mouse_target.target {
-        if let Ok(floor_transform) = floor_query.get(mouse_target.entity) {
-            Some(floor_transform.translation() + Vec3::Y)
-        } else {
-            None
+    let anchor = mouse_target
+        .target
+        .as_ref()
+        .and_then(|target| floor_query.get(target.entity).ok())
+        .map(|floor_transform| level.get_tile_at(floor_transform.translation()));
+
+    let Some(anchor) = anchor else {
+        for (entity, _, _) in placeholder.iter() {
+            commands.entity(entity).despawn_recursive();
         }
-    } else {
-        None
+        return;
     };
 
-    if let Some(placeholder_translation) = placeholder_translation {
-        if placeholder.is_empty() {
-            commands.spawn((
-                BuildingPlaceholder,
-                SceneBundle {
-                    scene: info.get_model(),
-                    transform: Transform::from_translation(placeholder_translation),
-                    ..default()
-                },
-            ));
-        } else {
-            for (_, mut transform) in placeholder.iter_mut() {
-                transform.translation = placeholder_translation;
-            }
-        }
+    let tiles = footprint_tiles(anchor, &info.get_footprint(), info.quarter_turns);
+    let is_valid = is_valid_placement(
+        &level,
+        &tiles,
+        footprints.iter().flat_map(|f| f.tiles.iter()),
+    );
+    let transform = Transform::from_translation(level.get_position_at(anchor))
+        .with_rotation(placement_rotation(info.quarter_turns));
+
+    if let Ok((_, mut placeholder_transform, mut placeholder)) = placeholder.get_single_mut() {
+        *placeholder_transform = transform;
+        placeholder.tiles = tiles;
+        placeholder.is_valid = is_valid;
     } else {
-        for (entity, _) in placeholder.iter() {
-            commands.entity(entity).despawn_recursive();
-        }
+        commands.spawn((
+            BuildingPlaceholder { tiles, is_valid },
+            SceneBundle {
+                scene: info.get_model(),
+                transform,
+                ..default()
+            },
+        ));
     }
 }
 
+/// The color last applied to the meshes of a placeholder or construction site.
 #[derive(Component)]
-pub struct PlaceholderRenderingFixed;
+pub struct PlaceholderRendering(Color);
 
 pub fn update_placeholder_render(
     placeholder: Query<
-        Entity,
         (
-            With<BuildingPlaceholder>,
-            Without<PlaceholderRenderingFixed>,
+            Entity,
+            Option<&BuildingPlaceholder>,
+            Option<&PlaceholderRendering>,
         ),
+        Or<(With<BuildingPlaceholder>, With<ConstructionSite>)>,
     >,
     children: Query<&Children>,
     mut commands: Commands,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    for scene in placeholder.iter() {
-        let color_mat = materials.add(Color::rgba(0., 1., 0., 0.5).into());
+    for (scene, placeholder, rendering) in placeholder.iter() {
+        let color = match placeholder {
+            Some(placeholder) if !placeholder.is_valid => Color::rgba(1., 0., 0., 0.5),
+            Some(_) => Color::rgba(0., 1., 0., 0.5),
+            None => Color::rgba(0.3, 0.5, 1., 0.5),
+        };
+
+        if rendering.is_some_and(|r| r.0 == color) {
+            continue;
+        }
+
+        let color_mat = materials.add(color.into());
         let mut found_children = false;
         for child in children.iter_descendants(scene) {
             if let Some(mut commands) = commands.get_entity(child) {
@@ -172,7 +290,94 @@ pub fn update_placeholder_render(
             found_children = true;
         }
         if found_children {
-            commands.entity(scene).insert(PlaceholderRenderingFixed);
+            commands.entity(scene).insert(PlaceholderRendering(color));
+        }
+    }
+}
+
+/// Spawns a finished building covering `tiles`. Its health is its build time, so tearing it
+/// down takes as long as putting it up.
+pub fn spawn_building(
+    commands: &mut Commands,
+    building: Arc<dyn BuildingInfo>,
+    transform: Transform,
+    tiles: Vec<GridPosition>,
+) -> Entity {
+    let collider = Collider::compound(
+        building
+            .get_footprint()
+            .iter()
+            .map(|offset| {
+                (
+                    Vec3::new(
+                        offset.x as f32 * TILE_SIZE,
+                        BUILDING_HALF_HEIGHT,
+                        offset.z as f32 * TILE_SIZE,
+                    ),
+                    Quat::IDENTITY,
+                    Collider::cuboid(HALF_TILE_SIZE, BUILDING_HALF_HEIGHT, HALF_TILE_SIZE),
+                )
+            })
+            .collect(),
+    );
+
+    let mut entity = commands.spawn((
+        SceneBundle {
+            scene: building.get_model(),
+            transform,
+            ..default()
+        },
+        Name::new(building.get_name()),
+        BuildingFootprint { tiles },
+        collider,
+        RigidBody::Fixed,
+        NavMeshAffector,
+        Selectable::default(),
+        Health::new(building.get_build_time()),
+        Demolishable::new(&building.get_cost()),
+    ));
+    building.on_completed(&mut entity);
+
+    entity.id()
+}
+
+pub fn confirm_building(
+    mut commands: Commands,
+    control: Query<&ActionState<ControlAction>>,
+    placeholder: Query<(Entity, &Transform, &BuildingPlaceholder)>,
+    info: Res<PlacingBuilding>,
+    mut stockpile: ResMut<Stockpile>,
+) {
+    for action_state in control.iter() {
+        let keep_placing = action_state.just_pressed(ControlAction::SelectAdditional);
+        if !action_state.just_pressed(ControlAction::Select) && !keep_placing {
+            continue;
+        }
+
+        let Ok((entity, transform, placeholder)) = placeholder.get_single() else {
+            continue;
+        };
+
+        if !placeholder.is_valid {
+            warn!("Can't place {} here", info.get_name());
+            continue;
+        }
+
+        if !stockpile.try_spend(&info.get_cost()) {
+            warn!("Not enough resources to build {}", info.get_name());
+            continue;
+        }
+
+        commands.entity(entity).despawn_recursive();
+        spawn_construction_site(
+            &mut commands,
+            info.info.clone(),
+            *transform,
+            placeholder.tiles.clone(),
+        );
+
+        if !keep_placing {
+            commands.remove_resource::<PlacingBuilding>();
         }
     }
 }
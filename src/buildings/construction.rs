@@ -0,0 +1,267 @@
+use crate::buildings::placement::BuildingFootprint;
+use crate::buildings::building::spawn_building;
+use crate::buildings::BuildingInfo;
+use crate::errands::{BuildErrand, Designation};
+use crate::game_level::{ResourceType, HALF_TILE_SIZE};
+use crate::grid::GridPosition;
+use crate::prelude::*;
+use crate::stockpile::Stockpile;
+use std::sync::Arc;
+
+pub struct ConstructionPlugin;
+
+impl Plugin for ConstructionPlugin {
+    fn build(&self, app: &mut App) {
+        app.load_assets::<ConstructionAssets>()
+            .add_systems(Update, cancel_construction_when_gizmo_clicked)
+            .add_systems(Update, complete_construction_sites);
+
+        add_base_gizmo_systems::<CancelConstructionGizmo>(app);
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct ConstructionAssets {
+    #[asset(path = "buildings/cancel.png")]
+    cancel_icon: Handle<Image>,
+}
+
+/// A building that has been paid for but still needs materials delivered and work done
+/// before it is finished.
+#[derive(Component)]
+pub struct ConstructionSite {
+    building: Arc<dyn BuildingInfo>,
+    delivered: Vec<ResourceType>,
+    progress: f32,
+}
+
+impl ConstructionSite {
+    pub fn new(building: Arc<dyn BuildingInfo>) -> Self {
+        Self {
+            building,
+            delivered: Vec::new(),
+            progress: 0.0,
+        }
+    }
+
+    /// Recreates a site from a save, keeping the delivered materials and the work done.
+    pub fn restore(
+        building: Arc<dyn BuildingInfo>,
+        delivered: Vec<ResourceType>,
+        progress: f32,
+    ) -> Self {
+        Self {
+            building,
+            delivered,
+            progress,
+        }
+    }
+
+    pub fn building(&self) -> &Arc<dyn BuildingInfo> {
+        &self.building
+    }
+
+    pub fn delivered(&self) -> &[ResourceType] {
+        &self.delivered
+    }
+
+    pub fn progress(&self) -> f32 {
+        self.progress
+    }
+
+    /// Materials that are neither delivered nor on their way to the site.
+    pub fn missing_materials(&self, in_transit: &[ResourceType]) -> Vec<ResourceType> {
+        let mut missing = self
+            .building
+            .get_cost()
+            .into_iter()
+            .flat_map(|(resource_type, amount)| std::iter::repeat_n(resource_type, amount as usize))
+            .collect_vec();
+
+        for resource_type in self.delivered.iter().chain(in_transit) {
+            if let Some(i) = missing.iter().position(|m| m == resource_type) {
+                missing.remove(i);
+            }
+        }
+
+        missing
+    }
+
+    pub fn deliver(&mut self, resource_type: ResourceType) {
+        self.delivered.push(resource_type);
+    }
+
+    pub fn has_all_materials(&self) -> bool {
+        self.missing_materials(&[]).is_empty()
+    }
+
+    pub fn work(&mut self, seconds: f32) {
+        self.progress += seconds;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.has_all_materials() && self.progress >= self.building.get_build_time()
+    }
+}
+
+/// Marks a resource item that was taken out of the stockpile to be delivered to a site.
+#[derive(Component)]
+pub struct ConstructionMaterial {
+    pub site: Entity,
+}
+
+pub fn spawn_construction_site(
+    commands: &mut Commands,
+    building: Arc<dyn BuildingInfo>,
+    transform: Transform,
+    tiles: Vec<GridPosition>,
+) -> Entity {
+    let name = format!("{} construction site", building.get_name());
+
+    let site = commands
+        .spawn((
+            SceneBundle {
+                scene: building.get_model(),
+                transform,
+                ..default()
+            },
+            ConstructionSite::new(building),
+            BuildingFootprint { tiles },
+            Name::new(name),
+            Collider::cuboid(HALF_TILE_SIZE, 1.0, HALF_TILE_SIZE),
+            Sensor,
+            Selectable::default(),
+        ))
+        .id();
+
+    commands
+        .entity(site)
+        .insert(Designation::new(site, BuildErrand::new(site)));
+
+    site
+}
+
+fn complete_construction_sites(
+    sites: Query<(Entity, &ConstructionSite, &Transform, &BuildingFootprint)>,
+    mut commands: Commands,
+) {
+    for (entity, site, transform, footprint) in sites.iter() {
+        if !site.is_finished() {
+            continue;
+        }
+
+        info!("Finished building {}", site.building.get_name());
+        commands.entity(entity).despawn_recursive();
+
+        spawn_building(
+            &mut commands,
+            site.building.clone(),
+            *transform,
+            footprint.tiles.clone(),
+        );
+    }
+}
+
+fn cancel_construction_when_gizmo_clicked(
+    q: Query<&Interaction, (Changed<Interaction>, With<GizmoTag<CancelConstructionGizmo>>)>,
+    sites: Query<(Entity, &ConstructionSite), With<Selected>>,
+    mut stockpile: ResMut<Stockpile>,
+    mut commands: Commands,
+) {
+    if !q.iter().any(|i| *i == Interaction::Pressed) {
+        return;
+    }
+
+    for (entity, site) in sites.iter() {
+        info!("Cancelled construction of {}", site.building.get_name());
+        stockpile.refund(&site.building.get_cost());
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[derive(Resource)]
+struct CancelConstructionGizmo(ButtonGizmo);
+
+impl HasBaseGizmo for CancelConstructionGizmo {
+    fn get_base_gizmo(&self) -> &ButtonGizmo {
+        &self.0
+    }
+}
+
+impl GizmoVisibility for CancelConstructionGizmo {
+    type WorldQuery = ();
+    type ReadOnlyWorldQuery = (With<Selected>, With<ConstructionSite>);
+
+    fn is_visible(query: &Query<Self::WorldQuery, Self::ReadOnlyWorldQuery>) -> bool {
+        !query.is_empty()
+    }
+}
+
+impl Gizmo for CancelConstructionGizmo {
+    type Assets = ConstructionAssets;
+
+    fn initialize(assets: &Self::Assets) -> Self {
+        Self(ButtonGizmo::new(assets.cancel_icon.clone(), "Cancel", 0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs::system::EntityCommands;
+
+    struct TestBuilding;
+
+    impl BuildingInfo for TestBuilding {
+        fn get_model(&self) -> Handle<Scene> {
+            Handle::default()
+        }
+
+        fn get_name(&self) -> String {
+            "Test".to_string()
+        }
+
+        fn get_cost(&self) -> Vec<(ResourceType, u32)> {
+            vec![(ResourceType::Ore, 2), (ResourceType::Crystal, 1)]
+        }
+
+        fn get_footprint(&self) -> Vec<GridPosition> {
+            vec![GridPosition::new(0, 0)]
+        }
+
+        fn get_build_time(&self) -> f32 {
+            3.0
+        }
+
+        fn on_completed(&self, _building: &mut EntityCommands) {}
+    }
+
+    #[test]
+    fn missing_materials_exclude_delivered_and_in_transit() {
+        let mut site = ConstructionSite::new(Arc::new(TestBuilding));
+        site.deliver(ResourceType::Ore);
+
+        assert_eq!(
+            site.missing_materials(&[ResourceType::Crystal]),
+            vec![ResourceType::Ore]
+        );
+        assert_eq!(
+            site.missing_materials(&[ResourceType::Ore]),
+            vec![ResourceType::Crystal]
+        );
+    }
+
+    #[test]
+    fn finishes_after_materials_and_build_time() {
+        let mut site = ConstructionSite::new(Arc::new(TestBuilding));
+        site.deliver(ResourceType::Ore);
+        site.deliver(ResourceType::Ore);
+
+        site.work(5.0);
+        assert!(!site.is_finished());
+
+        site.deliver(ResourceType::Crystal);
+        assert!(site.has_all_materials());
+        assert!(site.is_finished());
+    }
+}
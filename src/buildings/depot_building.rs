@@ -1,4 +1,9 @@
+use crate::buildings::building::{spawn_building, BuildingInfoWrapper};
+use crate::game_level::{GameLevel, ResourceType};
+use crate::grid::GridPosition;
 use crate::prelude::*;
+use bevy_ecs::system::EntityCommands;
+use std::sync::Arc;
 
 pub struct DepotBuildingPlugin;
 
@@ -11,7 +16,7 @@ impl Plugin for DepotBuildingPlugin {
 }
 
 #[derive(AssetCollection, Resource)]
-struct DepotAssets {
+pub struct DepotAssets {
     #[asset(path = "buildings/depot.gltf#Scene0")]
     depot: Handle<Scene>,
 
@@ -19,6 +24,26 @@ struct DepotAssets {
     depot_icon: Handle<Image>,
 }
 
+impl DepotAssets {
+    pub fn spawn_depot(
+        &self,
+        commands: &mut Commands,
+        level: &GameLevel,
+        position: GridPosition,
+    ) -> Entity {
+        spawn_building(
+            commands,
+            Arc::new(BuildingInfoWrapper(DepotBuilding::initialize(self))),
+            Transform::from_translation(level.get_position_at(position)),
+            vec![position],
+        )
+    }
+}
+
+/// Resources carried by raiders are delivered to the nearest depot.
+#[derive(Component)]
+pub struct Depot;
+
 #[derive(Clone)]
 struct DepotBuilding {
     model: Handle<Scene>,
@@ -36,6 +61,14 @@ impl Building for DepotBuilding {
         "Depot".to_string()
     }
 
+    fn get_cost() -> Vec<(ResourceType, u32)> {
+        vec![(ResourceType::Ore, 2)]
+    }
+
+    fn get_build_time() -> f32 {
+        5.0
+    }
+
     fn get_order() -> i32 {
         0
     }
@@ -51,6 +84,10 @@ impl Building for DepotBuilding {
             model: assets.depot.clone(),
         }
     }
+
+    fn on_completed(&self, building: &mut EntityCommands) {
+        building.insert(Depot);
+    }
 }
 
 #[derive(Clone)]
@@ -70,6 +107,18 @@ impl Building for AnotherDepotBuilding {
         "Another Depot".to_string()
     }
 
+    fn get_cost() -> Vec<(ResourceType, u32)> {
+        vec![(ResourceType::Ore, 1), (ResourceType::Crystal, 1)]
+    }
+
+    fn get_footprint() -> Vec<GridPosition> {
+        vec![GridPosition::new(0, 0), GridPosition::new(1, 0)]
+    }
+
+    fn get_build_time() -> f32 {
+        8.0
+    }
+
     fn get_order() -> i32 {
         1
     }
@@ -84,4 +133,8 @@ impl Building for AnotherDepotBuilding {
             model: assets.depot.clone(),
         }
     }
+
+    fn on_completed(&self, building: &mut EntityCommands) {
+        building.insert(Depot);
+    }
 }
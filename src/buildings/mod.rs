@@ -1,10 +1,24 @@
 mod building;
 mod building_menu;
+mod construction;
 mod depot_building;
+mod placement;
 
-use crate::buildings::building::{cancel_building, place_building, update_placeholder_render};
+use crate::buildings::building::{
+    cancel_building, confirm_building, place_building, rotate_building, update_placeholder_render,
+};
+use crate::buildings::placement::{
+    close_floors_under_buildings, has_removed_footprints, reopen_floors_without_buildings,
+};
+use crate::game_level::GameLevel;
 use crate::prelude::*;
-pub use building::{is_placing_building, Building, BuildingAppExtensions, BuildingInfo};
+pub use building::{
+    is_placing_building, spawn_building, Building, BuildingAppExtensions, BuildingInfo,
+    BuildingPlaceholder, BuildingTypes, PlacingBuilding,
+};
+pub use construction::{spawn_construction_site, ConstructionMaterial, ConstructionSite};
+pub use placement::BuildingFootprint;
+pub use depot_building::{Depot, DepotAssets};
 
 pub struct BuildingsPlugin;
 
@@ -13,11 +27,27 @@ impl Plugin for BuildingsPlugin {
         app.add_plugins((
             building_menu::BuildingMenuPlugin,
             depot_building::DepotBuildingPlugin,
+            construction::ConstructionPlugin,
         ))
         .add_systems(
             Update,
-            (place_building, cancel_building, update_placeholder_render)
+            (
+                rotate_building,
+                place_building,
+                confirm_building,
+                cancel_building,
+            )
+                .chain()
                 .run_if(is_placing_building),
+        )
+        .add_systems(Update, update_placeholder_render)
+        .add_systems(
+            Update,
+            (
+                close_floors_under_buildings,
+                reopen_floors_without_buildings.run_if(has_removed_footprints),
+            )
+                .run_if(resource_exists::<GameLevel>()),
         );
     }
 }
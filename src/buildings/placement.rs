@@ -0,0 +1,177 @@
+use crate::buildings::OpenForBuilding;
+use crate::errands::Standable;
+use crate::game_level::GameLevel;
+use crate::grid::GridPosition;
+use crate::prelude::*;
+use std::f32::consts::FRAC_PI_2;
+
+/// The level tiles covered by a building or construction site.
+#[derive(Component, Debug, Clone)]
+pub struct BuildingFootprint {
+    pub tiles: Vec<GridPosition>,
+}
+
+/// Rotates a footprint offset by the same quarter turns as `placement_rotation`.
+fn rotate_offset(offset: GridPosition, quarter_turns: u8) -> GridPosition {
+    (0..quarter_turns % 4).fold(offset, |o, _| GridPosition::new(o.z, -o.x))
+}
+
+pub fn footprint_tiles(
+    anchor: GridPosition,
+    footprint: &[GridPosition],
+    quarter_turns: u8,
+) -> Vec<GridPosition> {
+    footprint
+        .iter()
+        .map(|offset| rotate_offset(*offset, quarter_turns))
+        .map(|offset| GridPosition::new(anchor.x + offset.x, anchor.z + offset.z))
+        .collect()
+}
+
+pub fn placement_rotation(quarter_turns: u8) -> Quat {
+    Quat::from_rotation_y(FRAC_PI_2 * (quarter_turns % 4) as f32)
+}
+
+/// Buildings can only be placed on discovered open floor that isn't covered by another
+/// building yet.
+pub fn is_valid_placement<'a>(
+    level: &GameLevel,
+    tiles: &[GridPosition],
+    mut occupied: impl Iterator<Item = &'a GridPosition>,
+) -> bool {
+    let on_open_floor = tiles.iter().all(|t| {
+        level.within(t.x, t.z) && level.is_open(t.x, t.z) && level.is_discovered(t.x, t.z)
+    });
+
+    on_open_floor && !occupied.any(|o| tiles.contains(o))
+}
+
+pub fn close_floors_under_buildings(
+    floors: Query<(Entity, &GlobalTransform, Ref<OpenForBuilding>)>,
+    footprints: Query<Ref<BuildingFootprint>>,
+    level: Res<GameLevel>,
+    mut commands: Commands,
+) {
+    let any_new_footprint = footprints.iter().any(|f| f.is_added());
+
+    for (entity, transform, open) in floors.iter() {
+        if !any_new_footprint && !open.is_added() {
+            continue;
+        }
+
+        let tile = level.get_tile_at(transform.translation());
+        if footprints.iter().any(|f| f.tiles.contains(&tile)) {
+            commands.entity(entity).remove::<OpenForBuilding>();
+        }
+    }
+}
+
+pub fn reopen_floors_without_buildings(
+    floors: Query<(Entity, &GlobalTransform), (With<Standable>, Without<OpenForBuilding>)>,
+    footprints: Query<&BuildingFootprint>,
+    level: Res<GameLevel>,
+    mut commands: Commands,
+) {
+    for (entity, transform) in floors.iter() {
+        let tile = level.get_tile_at(transform.translation());
+
+        if level.is_open(tile.x, tile.z) && !footprints.iter().any(|f| f.tiles.contains(&tile)) {
+            commands.entity(entity).insert(OpenForBuilding);
+        }
+    }
+}
+
+pub fn has_removed_footprints(removed: RemovedComponents<BuildingFootprint>) -> bool {
+    !removed.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+
+    fn level() -> GameLevel {
+        let mut carved = Grid::new(7, 5, false);
+        for x in 1..4 {
+            for z in 1..4 {
+                carved.set(x, z, true);
+            }
+        }
+        carved.set(5, 2, true);
+
+        GameLevel::new_from_carved_tiles(carved, &[GridPosition::new(2, 2)])
+    }
+
+    #[test]
+    fn rotates_footprint_around_anchor() {
+        let footprint = [GridPosition::new(0, 0), GridPosition::new(1, 0)];
+        let anchor = GridPosition::new(2, 2);
+
+        assert_eq!(
+            footprint_tiles(anchor, &footprint, 0),
+            vec![anchor, GridPosition::new(3, 2)]
+        );
+        assert_eq!(
+            footprint_tiles(anchor, &footprint, 1),
+            vec![anchor, GridPosition::new(2, 1)]
+        );
+        assert_eq!(
+            footprint_tiles(anchor, &footprint, 2),
+            vec![anchor, GridPosition::new(1, 2)]
+        );
+        assert_eq!(
+            footprint_tiles(anchor, &footprint, 5),
+            footprint_tiles(anchor, &footprint, 1)
+        );
+    }
+
+    #[test]
+    fn offset_rotation_matches_model_rotation() {
+        let offset = GridPosition::new(1, 0);
+        let rotated = placement_rotation(1) * Vec3::new(1.0, 0.0, 0.0);
+        let expected = rotate_offset(offset, 1);
+
+        assert!((rotated.x - expected.x as f32).abs() < 0.001);
+        assert!((rotated.z - expected.z as f32).abs() < 0.001);
+    }
+
+    #[test]
+    fn rejects_walls_and_undiscovered_tiles() {
+        let level = level();
+        let none = std::iter::empty();
+
+        assert!(is_valid_placement(&level, &[GridPosition::new(2, 2)], none));
+        assert!(!is_valid_placement(
+            &level,
+            &[GridPosition::new(3, 3), GridPosition::new(4, 3)],
+            std::iter::empty()
+        ));
+        assert!(!is_valid_placement(
+            &level,
+            &[GridPosition::new(5, 2)],
+            std::iter::empty()
+        ));
+        assert!(!is_valid_placement(
+            &level,
+            &[GridPosition::new(-1, 2)],
+            std::iter::empty()
+        ));
+    }
+
+    #[test]
+    fn rejects_overlap_with_other_buildings() {
+        let level = level();
+        let existing = [GridPosition::new(2, 2)];
+
+        assert!(!is_valid_placement(
+            &level,
+            &[GridPosition::new(1, 2), GridPosition::new(2, 2)],
+            existing.iter()
+        ));
+        assert!(is_valid_placement(
+            &level,
+            &[GridPosition::new(1, 2), GridPosition::new(1, 3)],
+            existing.iter()
+        ));
+    }
+}
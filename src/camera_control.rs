@@ -1,7 +1,9 @@
+use crate::errands::PlayerMovable;
 use crate::prelude::*;
 use crate::ray_hit_helpers::get_hit;
-use crate::selection::WantToSelect;
+use crate::selection::{box_selection, WantToSelect};
 use crate::GameState;
+use bevy::ecs::query::Has;
 use bevy::window::PrimaryWindow;
 use leafwing_input_manager::action_state::ActionState;
 use leafwing_input_manager::user_input::InputKind;
@@ -16,11 +18,13 @@ impl Plugin for CameraControlPlugin {
             .add_systems(Update, (move_camera, rotate_camera, mouse_over_things).run_if(has_window_focus))
             .add_plugins(InputManagerPlugin::<ControlAction>::default())
             .add_systems(OnEnter(GameState::Playing), spawn_camera)
+            .add_systems(Startup, spawn_selection_box)
             .insert_resource(MouseTargetedEntity { target: None })
             .add_systems(Update,
-                (interact_with_things, select_things, clear_selection)
+                (interact_with_things, select_things, drag_select, clear_selection)
                     .run_if(has_window_focus)
                     .run_if(not(is_placing_building))
+                    .run_if(not(is_painting_designation))
                     .after(mouse_over_things),
             )
             .add_event::<InteractedWith>();
@@ -68,7 +72,28 @@ fn spawn_camera(mut commands: Commands) {
                     MouseButton::Right,
                     ControlAction::InteractAdditional,
                 )
+                .insert(Modifier::Alt, ControlAction::SelectTiles)
+                .insert(Modifier::Control, ControlAction::BindControlGroup)
+                .insert_multiple(
+                    [
+                        KeyCode::Key1,
+                        KeyCode::Key2,
+                        KeyCode::Key3,
+                        KeyCode::Key4,
+                        KeyCode::Key5,
+                        KeyCode::Key6,
+                        KeyCode::Key7,
+                        KeyCode::Key8,
+                        KeyCode::Key9,
+                    ]
+                    .into_iter()
+                    .zip(ControlAction::CONTROL_GROUPS),
+                )
                 .insert(KeyCode::Escape, ControlAction::Deselect)
+                .insert(KeyCode::Q, ControlAction::RotateBuildingLeft)
+                .insert(KeyCode::E, ControlAction::RotateBuildingRight)
+                .insert(KeyCode::F5, ControlAction::QuickSave)
+                .insert(KeyCode::F9, ControlAction::QuickLoad)
                 .build(),
         },
         CameraRotationTracker { angle_y: 0.0 },
@@ -84,9 +109,40 @@ pub enum ControlAction {
     MoveFast,
     Select,
     SelectAdditional,
+    /// Held while dragging to also select walls, floors and buildings.
+    SelectTiles,
     Interact,
     InteractAdditional,
     Deselect,
+    RotateBuildingLeft,
+    RotateBuildingRight,
+    QuickSave,
+    QuickLoad,
+    /// Held while pressing a control group number to bind the selection to it.
+    BindControlGroup,
+    ControlGroup1,
+    ControlGroup2,
+    ControlGroup3,
+    ControlGroup4,
+    ControlGroup5,
+    ControlGroup6,
+    ControlGroup7,
+    ControlGroup8,
+    ControlGroup9,
+}
+
+impl ControlAction {
+    pub const CONTROL_GROUPS: [ControlAction; 9] = [
+        ControlAction::ControlGroup1,
+        ControlAction::ControlGroup2,
+        ControlAction::ControlGroup3,
+        ControlAction::ControlGroup4,
+        ControlAction::ControlGroup5,
+        ControlAction::ControlGroup6,
+        ControlAction::ControlGroup7,
+        ControlAction::ControlGroup8,
+        ControlAction::ControlGroup9,
+    ];
 }
 
 const CAMERA_MOVE_RATE: f32 = 20.0;
@@ -197,6 +253,112 @@ fn select_things(
     }
 }
 
+const DRAG_THRESHOLD: f32 = 5.0;
+
+#[derive(Default)]
+struct DragSelection {
+    start: Option<Vec2>,
+    append: bool,
+}
+
+#[derive(Component)]
+struct SelectionBox;
+
+fn spawn_selection_box(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                border: UiRect::all(Val::Px(1.)),
+                display: Display::None,
+                ..default()
+            },
+            background_color: Color::WHITE.with_a(0.1).into(),
+            border_color: Color::WHITE.into(),
+            ..default()
+        },
+        SelectionBox,
+    ));
+}
+
+fn drag_select(
+    q: Query<(&ActionState<ControlAction>, &Camera, &GlobalTransform), With<Selector>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    ui_buttons: Query<&Interaction>,
+    selectable: Query<(Entity, &GlobalTransform, Has<PlayerMovable>), With<Selectable>>,
+    mut selection_box: Query<&mut Style, With<SelectionBox>>,
+    mut events: EventWriter<WantToSelect>,
+    mut drag: Local<DragSelection>,
+) {
+    let Ok((action_state, camera, camera_transform)) = q.get_single() else {
+        return;
+    };
+    let Some(cursor) = windows.get_single().ok().and_then(|w| w.cursor_position()) else {
+        return;
+    };
+
+    let pressed = action_state.pressed(ControlAction::Select)
+        || action_state.pressed(ControlAction::SelectAdditional);
+
+    if drag.start.is_none() && pressed {
+        if ui_buttons.iter().any(|i| *i != Interaction::None) {
+            return;
+        }
+
+        drag.start = Some(cursor);
+        drag.append = action_state.pressed(ControlAction::SelectAdditional);
+    }
+
+    let Some(start) = drag.start else {
+        return;
+    };
+
+    let rect = Rect::from_corners(start, cursor);
+    let dragging = rect.width() > DRAG_THRESHOLD || rect.height() > DRAG_THRESHOLD;
+
+    if let Ok(mut style) = selection_box.get_single_mut() {
+        if dragging && pressed {
+            style.display = Display::Flex;
+            style.left = Val::Px(rect.min.x);
+            style.top = Val::Px(rect.min.y);
+            style.width = Val::Px(rect.width());
+            style.height = Val::Px(rect.height());
+        } else if style.display != Display::None {
+            style.display = Display::None;
+        }
+    }
+
+    if pressed {
+        return;
+    }
+
+    drag.start = None;
+
+    if !dragging {
+        return;
+    }
+
+    let candidates = selectable
+        .iter()
+        .filter_map(|(entity, transform, is_unit)| {
+            camera
+                .world_to_viewport(camera_transform, transform.translation())
+                .map(|position| (entity, position, is_unit))
+        });
+    let include_everything = action_state.pressed(ControlAction::SelectTiles);
+    let in_box = box_selection(rect, candidates, include_everything)
+        .into_iter()
+        .map(WantToSelect::Additionally)
+        .collect_vec();
+
+    info!("Box selected {} entities", in_box.len());
+
+    if !drag.append {
+        events.send(WantToSelect::Clear);
+    }
+    events.send_batch(in_box);
+}
+
 #[derive(Component)]
 pub struct PlayerInteractable;
 
@@ -0,0 +1,476 @@
+use crate::game_level::{GameLevel, ResourceType, Seam, WallType};
+use crate::grid::{flood_fill_grid, Grid, GridPosition};
+use crate::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::VecDeque;
+
+/// Replaces the map file with a generated cave when present.
+#[derive(Resource)]
+pub struct Skirmish {
+    pub seed: u64,
+    pub parameters: CaveParameters,
+}
+
+#[derive(Debug, Clone)]
+pub struct CaveParameters {
+    pub width: i32,
+    pub height: i32,
+    /// Chance for a tile to start out open before the caverns are smoothed.
+    pub initial_open_chance: f32,
+    pub smoothing_iterations: u32,
+    /// Caverns with fewer tiles than this are filled back in.
+    pub min_cavern_size: usize,
+    pub start_cavern_radius: i32,
+    pub loose_rock_chance: f32,
+    pub hard_rock_chance: f32,
+    pub ore_seams: u32,
+    pub crystal_seams: u32,
+    pub seam_length: u32,
+    pub seam_yield: u32,
+}
+
+impl Default for CaveParameters {
+    fn default() -> Self {
+        Self {
+            width: 40,
+            height: 40,
+            initial_open_chance: 0.45,
+            smoothing_iterations: 4,
+            min_cavern_size: 6,
+            start_cavern_radius: 2,
+            loose_rock_chance: 0.3,
+            hard_rock_chance: 0.2,
+            ore_seams: 6,
+            crystal_seams: 3,
+            seam_length: 4,
+            seam_yield: 3,
+        }
+    }
+}
+
+const RAIDER_COUNT: usize = 2;
+
+#[derive(Debug)]
+pub struct GeneratedCave {
+    pub level: GameLevel,
+    /// Closest to the center of the start cavern first.
+    pub raider_spawns: Vec<GridPosition>,
+    pub depot: GridPosition,
+}
+
+pub fn generate_cave(seed: u64, parameters: &CaveParameters) -> GeneratedCave {
+    assert!(
+        parameters.start_cavern_radius >= 1,
+        "Start cavern should have room for the raiders and the depot"
+    );
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    let mut open = Grid::new(parameters.width, parameters.height, false);
+    for pos in interior_positions(&open) {
+        open.set(
+            pos.x,
+            pos.z,
+            rng.gen_bool(parameters.initial_open_chance as f64),
+        );
+    }
+
+    for _ in 0..parameters.smoothing_iterations {
+        open = smooth(&open);
+    }
+
+    let start = GridPosition::new(parameters.width / 2, parameters.height / 2);
+    carve_start_cavern(&mut open, start, parameters.start_cavern_radius);
+
+    remove_small_caverns(&mut open, start, parameters.min_cavern_size);
+    connect_caverns(&mut open, start, &mut rng);
+    remove_thin_walls(&mut open);
+
+    let mut level = GameLevel::new_from_open_tiles(open.clone());
+    for pos in level.iter_tiles().collect_vec() {
+        level.set_wall_type(
+            pos.x,
+            pos.z,
+            pick_wall_type(&open, pos, parameters, &mut rng),
+        );
+    }
+
+    place_seams(&mut level, parameters, &mut rng);
+
+    // The raiders get the tiles closest to the start, the depot the next one.
+    let is_open = |x, z| *open.get(x, z).unwrap_or(&false);
+    let mut start_tiles = flood_fill_grid(&open, start.x, start.z, is_open)
+        .into_iter()
+        .unique()
+        .sorted_by_key(|p| ((p.x - start.x).pow(2) + (p.z - start.z).pow(2), p.x, p.z));
+    let raider_spawns = start_tiles.by_ref().take(RAIDER_COUNT).collect_vec();
+    let depot = start_tiles
+        .next()
+        .expect("Start cavern should be carved large enough for the depot");
+
+    GeneratedCave {
+        level,
+        raider_spawns,
+        depot,
+    }
+}
+
+fn interior_positions<T>(grid: &Grid<T>) -> impl Iterator<Item = GridPosition> {
+    let height = grid.height();
+    (1..grid.width() - 1).flat_map(move |x| (1..height - 1).map(move |z| GridPosition::new(x, z)))
+}
+
+fn count_walls_around(open: &Grid<bool>, pos: GridPosition) -> usize {
+    (-1..=1)
+        .cartesian_product(-1..=1)
+        .filter(|(dx, dz)| *dx != 0 || *dz != 0)
+        .filter(|(dx, dz)| !*open.get(pos.x + dx, pos.z + dz).unwrap_or(&false))
+        .count()
+}
+
+fn smooth(open: &Grid<bool>) -> Grid<bool> {
+    let mut result = open.clone();
+
+    for pos in interior_positions(open) {
+        let walls = count_walls_around(open, pos);
+        if walls > 4 {
+            result.set(pos.x, pos.z, false);
+        } else if walls < 4 {
+            result.set(pos.x, pos.z, true);
+        }
+    }
+
+    result
+}
+
+fn carve_start_cavern(open: &mut Grid<bool>, start: GridPosition, radius: i32) {
+    for x in start.x - radius..=start.x + radius {
+        for z in start.z - radius..=start.z + radius {
+            let inside = (x - start.x).pow(2) + (z - start.z).pow(2) <= radius * radius;
+            if inside && is_interior(open, x, z) {
+                open.set(x, z, true);
+            }
+        }
+    }
+}
+
+fn is_interior<T>(grid: &Grid<T>, x: i32, z: i32) -> bool {
+    x > 0 && z > 0 && x < grid.width() - 1 && z < grid.height() - 1
+}
+
+fn find_caverns(open: &Grid<bool>) -> Vec<Vec<GridPosition>> {
+    let mut assigned = Grid::new(open.width(), open.height(), false);
+    let mut caverns = Vec::new();
+
+    for pos in interior_positions(open) {
+        if !*open.get(pos.x, pos.z).unwrap() || *assigned.get(pos.x, pos.z).unwrap() {
+            continue;
+        }
+
+        let cavern = flood_fill_grid(open, pos.x, pos.z, |x, z| *open.get(x, z).unwrap_or(&false))
+            .into_iter()
+            .unique()
+            .collect_vec();
+
+        for tile in cavern.iter() {
+            assigned.set(tile.x, tile.z, true);
+        }
+
+        caverns.push(cavern);
+    }
+
+    caverns
+}
+
+fn remove_small_caverns(open: &mut Grid<bool>, start: GridPosition, min_size: usize) {
+    for cavern in find_caverns(open) {
+        if cavern.len() < min_size && !cavern.contains(&start) {
+            for tile in cavern {
+                open.set(tile.x, tile.z, false);
+            }
+        }
+    }
+}
+
+/// Digs tunnels until every cavern is reachable from the start cavern, always
+/// connecting the cavern closest to the already connected area first.
+fn connect_caverns(open: &mut Grid<bool>, start: GridPosition, rng: &mut ChaCha8Rng) {
+    while let Some((from, to)) = closest_unconnected_tile(open, start) {
+        dig_tunnel(open, from, to, rng);
+    }
+}
+
+/// Searches outwards through the rock from every tile connected to `start` at once, so the
+/// first open tile it runs into belongs to the closest unconnected cavern. Returns that tile
+/// and the connected tile the search reached it from.
+fn closest_unconnected_tile(
+    open: &Grid<bool>,
+    start: GridPosition,
+) -> Option<(GridPosition, GridPosition)> {
+    let mut origins = Grid::new(open.width(), open.height(), None);
+    let mut frontier = VecDeque::new();
+
+    let is_open = |x, z| *open.get(x, z).unwrap_or(&false);
+    for tile in flood_fill_grid(open, start.x, start.z, is_open) {
+        if origins.get(tile.x, tile.z).unwrap().is_none() {
+            origins.set(tile.x, tile.z, Some(tile));
+            frontier.push_back(tile);
+        }
+    }
+
+    while let Some(tile) = frontier.pop_front() {
+        let origin = origins.get(tile.x, tile.z).unwrap().unwrap();
+
+        for (dx, dz) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let next = GridPosition::new(tile.x + dx, tile.z + dz);
+            if !is_interior(open, next.x, next.z) || origins.get(next.x, next.z).unwrap().is_some()
+            {
+                continue;
+            }
+
+            if *open.get(next.x, next.z).unwrap() {
+                return Some((next, origin));
+            }
+
+            origins.set(next.x, next.z, Some(origin));
+            frontier.push_back(next);
+        }
+    }
+
+    None
+}
+
+fn dig_tunnel(open: &mut Grid<bool>, from: GridPosition, to: GridPosition, rng: &mut ChaCha8Rng) {
+    let mut current = from;
+
+    while current != to {
+        let step_x = current.x != to.x && (current.z == to.z || rng.gen_bool(0.5));
+        if step_x {
+            current.x += (to.x - current.x).signum();
+        } else {
+            current.z += (to.z - current.z).signum();
+        }
+        open.set(current.x, current.z, true);
+    }
+}
+
+/// Opens walls that are only a single tile thick, the same way
+/// `GameLevel::remove_wall` would once a neighbouring wall is mined.
+fn remove_thin_walls(open: &mut Grid<bool>) {
+    loop {
+        let thin = interior_positions(open)
+            .filter(|p| !*open.get(p.x, p.z).unwrap())
+            .filter(|p| {
+                let is_open = |x, z| *open.get(x, z).unwrap_or(&false);
+                (is_open(p.x - 1, p.z) && is_open(p.x + 1, p.z))
+                    || (is_open(p.x, p.z - 1) && is_open(p.x, p.z + 1))
+            })
+            .collect_vec();
+
+        if thin.is_empty() {
+            return;
+        }
+
+        for pos in thin {
+            open.set(pos.x, pos.z, true);
+        }
+    }
+}
+
+fn pick_wall_type(
+    open: &Grid<bool>,
+    pos: GridPosition,
+    parameters: &CaveParameters,
+    rng: &mut ChaCha8Rng,
+) -> WallType {
+    if !is_interior(open, pos.x, pos.z) {
+        return WallType::SolidRock;
+    }
+
+    let roll: f32 = rng.gen();
+    if roll < parameters.hard_rock_chance {
+        WallType::HardRock
+    } else if roll < parameters.hard_rock_chance + parameters.loose_rock_chance {
+        WallType::LooseRock
+    } else {
+        WallType::Dirt
+    }
+}
+
+/// Seams are short random walks through minable walls.
+fn place_seams(level: &mut GameLevel, parameters: &CaveParameters, rng: &mut ChaCha8Rng) {
+    let candidates = level
+        .iter_tiles()
+        .filter(|p| !level.is_open(p.x, p.z) && level.wall_type(p.x, p.z).mining_time().is_some())
+        .collect_vec();
+
+    if candidates.is_empty() {
+        return;
+    }
+
+    let resource_types =
+        std::iter::repeat_n(ResourceType::Ore, parameters.ore_seams as usize).chain(
+            std::iter::repeat_n(ResourceType::Crystal, parameters.crystal_seams as usize),
+        );
+
+    for resource_type in resource_types {
+        let mut current = candidates[rng.gen_range(0..candidates.len())];
+
+        for _ in 0..parameters.seam_length {
+            if level.seam(current.x, current.z).is_none() {
+                level.set_seam(
+                    current.x,
+                    current.z,
+                    Some(Seam {
+                        resource_type,
+                        yield_count: parameters.seam_yield,
+                    }),
+                );
+            }
+
+            let (dx, dz) = [(1, 0), (-1, 0), (0, 1), (0, -1)][rng.gen_range(0..4)];
+            let next = GridPosition::new(current.x + dx, current.z + dz);
+            if candidates.contains(&next) {
+                current = next;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reachable_from_start(cave: &GeneratedCave) -> Vec<GridPosition> {
+        let level = &cave.level;
+        let start = cave.raider_spawns[0];
+        let grid = Grid::new(level.width(), level.height(), ());
+        flood_fill_grid(&grid, start.x, start.z, |x, z| level.is_open(x, z))
+            .into_iter()
+            .unique()
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_generates_same_cave() {
+        let parameters = CaveParameters::default();
+
+        let first = generate_cave(42, &parameters);
+        let second = generate_cave(42, &parameters);
+
+        assert_eq!(first.level, second.level);
+        assert_eq!(first.raider_spawns, second.raider_spawns);
+        assert_eq!(first.depot, second.depot);
+    }
+
+    #[test]
+    fn different_seeds_generate_different_caves() {
+        let parameters = CaveParameters::default();
+
+        let first = generate_cave(1, &parameters);
+        let second = generate_cave(2, &parameters);
+
+        assert_ne!(first.level, second.level);
+    }
+
+    #[test]
+    fn all_open_tiles_are_connected_to_start() {
+        let parameters = CaveParameters::default();
+
+        for seed in 0..20 {
+            let cave = generate_cave(seed, &parameters);
+            let reachable = reachable_from_start(&cave);
+            let open_count = cave
+                .level
+                .iter_tiles()
+                .filter(|p| cave.level.is_open(p.x, p.z))
+                .count();
+
+            let start = cave.raider_spawns[0];
+            assert!(cave.level.is_open(start.x, start.z));
+            assert_eq!(
+                reachable.len(),
+                open_count,
+                "Seed {seed} has unreachable caverns"
+            );
+        }
+    }
+
+    #[test]
+    fn tunnels_lead_to_the_closest_cavern() {
+        // Start on the left, a small cavern in the middle and one far down on the right.
+        let rows = [
+            "#######", "#o#o###", "#######", "#######", "#####o#", "#######",
+        ];
+        let mut open = Grid::from_rows(&rows);
+
+        let start = GridPosition::new(1, 1);
+        assert_eq!(
+            closest_unconnected_tile(&open, start),
+            Some((GridPosition::new(3, 1), GridPosition::new(1, 1)))
+        );
+
+        connect_caverns(&mut open, start, &mut ChaCha8Rng::seed_from_u64(0));
+
+        assert_eq!(find_caverns(&open).len(), 1);
+        assert_eq!(closest_unconnected_tile(&open, start), None);
+    }
+
+    #[test]
+    fn raiders_and_depot_start_on_separate_open_tiles() {
+        let parameters = CaveParameters {
+            start_cavern_radius: 1,
+            ..default()
+        };
+
+        for seed in 0..20 {
+            let cave = generate_cave(seed, &parameters);
+            let reachable = reachable_from_start(&cave);
+            let mut tiles = cave.raider_spawns.clone();
+            tiles.push(cave.depot);
+
+            assert_eq!(cave.raider_spawns.len(), RAIDER_COUNT);
+            assert_eq!(tiles.iter().unique().count(), tiles.len());
+            assert!(tiles.iter().all(|t| reachable.contains(t)), "Seed {seed}");
+        }
+    }
+
+    #[test]
+    fn border_is_solid_rock() {
+        let parameters = CaveParameters::default();
+        let cave = generate_cave(7, &parameters);
+        let level = &cave.level;
+
+        for pos in level.iter_tiles() {
+            if pos.x == 0 || pos.z == 0 || pos.x == level.width() - 1 || pos.z == level.height() - 1
+            {
+                assert!(!level.is_open(pos.x, pos.z));
+                assert_eq!(level.wall_type(pos.x, pos.z), WallType::SolidRock);
+            }
+        }
+    }
+
+    #[test]
+    fn seams_are_placed_in_minable_walls() {
+        let parameters = CaveParameters::default();
+        let cave = generate_cave(3, &parameters);
+
+        let level = &cave.level;
+        let seams = level
+            .iter_tiles()
+            .filter_map(|p| level.seam(p.x, p.z).map(|s| (p, s)))
+            .collect_vec();
+
+        assert!(seams
+            .iter()
+            .any(|(_, s)| s.resource_type == ResourceType::Ore));
+        assert!(seams
+            .iter()
+            .any(|(_, s)| s.resource_type == ResourceType::Crystal));
+
+        for (pos, _) in seams {
+            assert!(!level.is_open(pos.x, pos.z));
+            assert!(level.wall_type(pos.x, pos.z).mining_time().is_some());
+        }
+    }
+}
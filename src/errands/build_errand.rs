@@ -0,0 +1,215 @@
+use crate::buildings::{ConstructionMaterial, ConstructionSite, Depot};
+use crate::errands::carry_to_depot_errand::{drop_item, pick_up_item};
+use crate::errands::move_to_position_errand::Approaching;
+use crate::errands::{
+    Carrying, ErrandFailureReason, ErrandTarget, ErrandsV2AppExtensions, MoveToPosition,
+    QueuedErrand, QueuedErrandFailureBuilder, QueuedErrandImpl, SavedErrand, WorkingOnErrand,
+};
+use crate::game_level::TILE_SIZE;
+use crate::prelude::*;
+use crate::resource_items::{ResourceItem, ResourceModels};
+use bevy::math::Vec3Swizzles;
+
+pub struct BuildErrandPlugin;
+
+impl Plugin for BuildErrandPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            execute_build.run_if(resource_exists::<ResourceModels>()),
+        )
+        .add_errand::<BuildErrand>();
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BuildErrand {
+    site: Entity,
+}
+
+impl BuildErrand {
+    pub fn new(site: Entity) -> Self {
+        Self { site }
+    }
+}
+
+impl Errand for BuildErrand {
+    type WorkerComponent = Builder;
+
+    fn on_enqueued<TEnqueued: QueuedErrand>(&self, queued: &mut TEnqueued) {
+        queued.fail_if_entity_missing(self.site);
+    }
+
+    fn get_errand_type_order() -> i32 {
+        7000
+    }
+
+    fn name() -> &'static str {
+        "Build"
+    }
+
+    fn target(&self) -> Option<ErrandTarget<Entity>> {
+        Some(ErrandTarget::Entity(self.site))
+    }
+
+    fn save(&self) -> SavedErrand<Entity> {
+        SavedErrand::Build { site: self.site }
+    }
+}
+
+#[derive(Component)]
+pub struct Builder;
+
+fn is_within_reach(target: Vec3, worker: Vec3) -> bool {
+    (target - worker).xz().length() <= TILE_SIZE
+}
+
+/// Sends the builder to `target`, unless it was already sent there and didn't make it, in
+/// which case the errand fails.
+fn move_to(
+    commands: &mut Commands,
+    builder: Entity,
+    errand: &mut WorkingOnErrand<BuildErrand>,
+    approaching: Option<&Approaching>,
+    queue: &mut ErrandQueue,
+    target: Vec3,
+) {
+    if approaching.is_some_and(|a| a.is_for(errand.id(), target)) {
+        info!("Could not get to {:?}. Failing errand.", target);
+        errand.fail(ErrandFailureReason::PathNotFound);
+        return;
+    }
+
+    commands
+        .entity(builder)
+        .insert(Approaching::new(errand.id(), target));
+
+    let site = errand.site;
+    queue.prepend_errand(|id| {
+        let mut e = QueuedErrandImpl::new(id, MoveToPosition::new(target, Some(TILE_SIZE)));
+        e.fail_if_entity_missing(site);
+
+        e
+    });
+}
+
+/// Builders fetch the missing materials from the nearest depot one at a time, then work on
+/// the site until it is finished.
+fn execute_build(
+    mut builders: Query<(
+        Entity,
+        &mut WorkingOnErrand<BuildErrand>,
+        &GlobalTransform,
+        &mut ErrandQueue,
+        Option<&Carrying>,
+        Option<&Approaching>,
+    )>,
+    mut sites: Query<(&mut ConstructionSite, &GlobalTransform)>,
+    materials: Query<(&ConstructionMaterial, &ResourceItem)>,
+    depots: Query<&GlobalTransform, With<Depot>>,
+    resource_models: Res<ResourceModels>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (builder, mut errand, builder_transform, mut queue, carrying, approaching) in
+        builders.iter_mut()
+    {
+        let builder_position = builder_transform.translation();
+
+        let Ok((mut site, site_transform)) = sites.get_mut(errand.site) else {
+            info!("Construction site no longer exists. Removing errand.");
+            errand.done();
+            continue;
+        };
+        let site_position = site_transform.translation();
+
+        if let Some(carrying) = carrying {
+            match materials.get(carrying.item) {
+                Ok((material, item)) if material.site == errand.site => {
+                    if !is_within_reach(site_position, builder_position) {
+                        move_to(
+                            &mut commands,
+                            builder,
+                            &mut errand,
+                            approaching,
+                            &mut queue,
+                            site_position,
+                        );
+                        continue;
+                    }
+
+                    info!("Delivered {:?} to construction site", item.resource_type);
+                    site.deliver(item.resource_type);
+                    commands.entity(builder).remove::<(Carrying, Approaching)>();
+                    commands.entity(carrying.item).despawn_recursive();
+                }
+                _ => drop_item(&mut commands, builder, carrying),
+            }
+            continue;
+        }
+
+        let in_transit = materials
+            .iter()
+            .filter(|(m, _)| m.site == errand.site)
+            .map(|(_, item)| item.resource_type)
+            .collect_vec();
+
+        if let Some(resource_type) = site.missing_materials(&in_transit).first().copied() {
+            let nearest_depot = depots
+                .iter()
+                .map(|t| t.translation())
+                .min_by(|a, b| {
+                    a.distance_squared(builder_position)
+                        .total_cmp(&b.distance_squared(builder_position))
+                });
+
+            let Some(depot_position) = nearest_depot else {
+                warn!("No depot to fetch construction materials from. Failing errand.");
+                errand.fail(ErrandFailureReason::NoDepot);
+                continue;
+            };
+
+            if !is_within_reach(depot_position, builder_position) {
+                move_to(
+                    &mut commands,
+                    builder,
+                    &mut errand,
+                    approaching,
+                    &mut queue,
+                    depot_position,
+                );
+                continue;
+            }
+
+            commands.entity(builder).remove::<Approaching>();
+            let item = resource_models.spawn(&mut commands, resource_type, depot_position);
+            commands
+                .entity(item)
+                .insert(ConstructionMaterial { site: errand.site });
+            pick_up_item(&mut commands, builder, item, true);
+            continue;
+        }
+
+        if !site.has_all_materials() {
+            // Someone else is still bringing the last materials.
+            continue;
+        }
+
+        if !is_within_reach(site_position, builder_position) {
+            move_to(
+                &mut commands,
+                builder,
+                &mut errand,
+                approaching,
+                &mut queue,
+                site_position,
+            );
+            continue;
+        }
+
+        if approaching.is_some() {
+            commands.entity(builder).remove::<Approaching>();
+        }
+        site.work(time.delta_seconds());
+    }
+}
@@ -0,0 +1,283 @@
+use crate::buildings::Depot;
+use crate::errands::move_to_position_errand::Approaching;
+use crate::errands::{
+    Designation, ErrandFailureReason, ErrandTarget, ErrandsV2AppExtensions, IsWorking,
+    MoveToPosition, QueuedErrand, QueuedErrandFailureBuilder, QueuedErrandImpl, SavedErrand,
+    WorkingOnErrand,
+};
+use crate::game_level::{HALF_TILE_SIZE, TILE_SIZE};
+use crate::prelude::*;
+use crate::resource_items::ResourceItem;
+use crate::stockpile::Stockpile;
+use bevy::ecs::query::Has;
+use bevy::math::Vec3Swizzles;
+
+const PICKUP_DISTANCE: f32 = HALF_TILE_SIZE;
+const DEPOSIT_DISTANCE: f32 = TILE_SIZE;
+const CARRY_OFFSET: Vec3 = Vec3::new(0.0, 1.0, 0.8);
+
+pub struct CarryToDepotErrandPlugin;
+
+impl Plugin for CarryToDepotErrandPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                designate_collectables,
+                execute_carry_to_depot,
+                drop_carried_items_when_idle,
+            ),
+        )
+        .add_errand::<CarryToDepotErrand>();
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CarryToDepotErrand {
+    item: Entity,
+}
+
+impl CarryToDepotErrand {
+    pub fn new(item: Entity) -> Self {
+        Self { item }
+    }
+}
+
+impl Errand for CarryToDepotErrand {
+    type WorkerComponent = Hauler;
+
+    fn on_enqueued<TEnqueued: QueuedErrand>(&self, queued: &mut TEnqueued) {
+        queued.fail_if_entity_missing(self.item);
+    }
+
+    fn get_errand_type_order() -> i32 {
+        6000
+    }
+
+    fn name() -> &'static str {
+        "Haul"
+    }
+
+    fn target(&self) -> Option<ErrandTarget<Entity>> {
+        Some(ErrandTarget::Entity(self.item))
+    }
+
+    fn save(&self) -> SavedErrand<Entity> {
+        SavedErrand::CarryToDepot { item: self.item }
+    }
+}
+
+#[derive(Component)]
+pub struct Hauler;
+
+/// An item lying on the ground that can be picked up and carried to a depot.
+#[derive(Component)]
+pub struct Collectable;
+
+#[derive(Component, Debug)]
+pub struct Carrying {
+    pub item: Entity,
+    /// Set for items that are already accounted for elsewhere, like construction materials,
+    /// which must not end up back in the stockpile when put down.
+    pub discard_when_dropped: bool,
+}
+
+fn designate_collectables(
+    items: Query<Entity, (Added<Collectable>, Without<Designation>)>,
+    mut commands: Commands,
+) {
+    for item in items.iter() {
+        commands
+            .entity(item)
+            .insert(Designation::new(item, CarryToDepotErrand::new(item)));
+    }
+}
+
+fn horizontal_distance(a: Vec3, b: Vec3) -> f32 {
+    (a - b).xz().length()
+}
+
+fn execute_carry_to_depot(
+    mut haulers: Query<(
+        Entity,
+        &mut WorkingOnErrand<CarryToDepotErrand>,
+        &GlobalTransform,
+        &mut ErrandQueue,
+        Option<&Carrying>,
+        Option<&Approaching>,
+    )>,
+    items: Query<(&GlobalTransform, &ResourceItem, Has<Collectable>)>,
+    depots: Query<&GlobalTransform, With<Depot>>,
+    mut stockpile: ResMut<Stockpile>,
+    mut commands: Commands,
+) {
+    for (hauler, mut errand, hauler_transform, mut queue, carrying, approaching) in
+        haulers.iter_mut()
+    {
+        let hauler_position = hauler_transform.translation();
+
+        // Anything already in hand is delivered before picking up the errand's item.
+        if let Some(carrying) = carrying {
+            if carrying.discard_when_dropped {
+                drop_item(&mut commands, hauler, carrying);
+                continue;
+            }
+
+            let nearest_depot = depots
+                .iter()
+                .map(|t| t.translation())
+                .min_by(|a, b| {
+                    a.distance_squared(hauler_position)
+                        .total_cmp(&b.distance_squared(hauler_position))
+                });
+
+            let Some(depot_position) = nearest_depot else {
+                warn!("No depot to deliver {:?} to. Dropping it.", carrying.item);
+                drop_item(&mut commands, hauler, carrying);
+                errand.done();
+                continue;
+            };
+
+            if horizontal_distance(depot_position, hauler_position) > DEPOSIT_DISTANCE {
+                if approaching.is_some_and(|a| a.is_for(errand.id(), depot_position)) {
+                    info!("Could not get to the depot. Failing errand.");
+                    errand.fail(ErrandFailureReason::PathNotFound);
+                    continue;
+                }
+
+                commands
+                    .entity(hauler)
+                    .insert(Approaching::new(errand.id(), depot_position));
+                queue.prepend_errand(|id| {
+                    QueuedErrandImpl::new(id, MoveToPosition::new(depot_position, Some(TILE_SIZE)))
+                });
+                continue;
+            }
+
+            if let Ok((_, item, _)) = items.get(carrying.item) {
+                info!("Deposited {:?} at depot", item.resource_type);
+                stockpile.add(item.resource_type, 1);
+            }
+
+            commands.entity(hauler).remove::<(Carrying, Approaching)>();
+            commands.entity(carrying.item).despawn_recursive();
+
+            if carrying.item == errand.item {
+                errand.done();
+            }
+            continue;
+        }
+
+        let Ok((item_transform, _, true)) = items.get(errand.item) else {
+            info!("Item to carry is gone or already picked up. Removing errand.");
+            errand.done();
+            continue;
+        };
+
+        let item_position = item_transform.translation();
+        if horizontal_distance(item_position, hauler_position) > PICKUP_DISTANCE {
+            if approaching.is_some_and(|a| a.is_for(errand.id(), item_position)) {
+                info!("Could not get to {:?}. Failing errand.", errand.item);
+                errand.fail(ErrandFailureReason::PathNotFound);
+                continue;
+            }
+
+            commands
+                .entity(hauler)
+                .insert(Approaching::new(errand.id(), item_position));
+            queue.prepend_errand(|id| {
+                let mut e = QueuedErrandImpl::new(id, MoveToPosition::new(item_position, None));
+                e.fail_if_entity_missing(errand.item);
+
+                e
+            });
+            continue;
+        }
+
+        info!("Picked up {:?}", errand.item);
+        commands.entity(hauler).remove::<Approaching>();
+        pick_up_item(&mut commands, hauler, errand.item, false);
+    }
+}
+
+pub fn pick_up_item(
+    commands: &mut Commands,
+    carrier: Entity,
+    item: Entity,
+    discard_when_dropped: bool,
+) {
+    commands
+        .entity(item)
+        .remove::<(Collectable, RigidBody, Collider)>()
+        .set_parent(carrier)
+        .insert(Transform::from_translation(CARRY_OFFSET));
+    commands.entity(carrier).insert(Carrying {
+        item,
+        discard_when_dropped,
+    });
+}
+
+pub fn drop_item(commands: &mut Commands, carrier: Entity, carrying: &Carrying) {
+    commands.entity(carrier).remove::<Carrying>();
+
+    let Some(mut item) = commands.get_entity(carrying.item) else {
+        return;
+    };
+
+    if carrying.discard_when_dropped {
+        item.despawn_recursive();
+    } else {
+        item.remove_parent_in_place()
+            .insert((Collectable, RigidBody::Dynamic, Collider::ball(0.5)));
+    }
+}
+
+/// Raiders whose carry errand got cancelled put their item back down so someone else can
+/// deliver it.
+fn drop_carried_items_when_idle(
+    haulers: Query<(Entity, &Carrying), Without<IsWorking>>,
+    mut commands: Commands,
+) {
+    for (hauler, carrying) in haulers.iter() {
+        info!("Dropping {:?} as the carrier is idle", carrying.item);
+        drop_item(&mut commands, hauler, carrying);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errands::test_harness::*;
+    use crate::errands::ErrandFailed;
+    use crate::game_level::ResourceType;
+
+    #[test]
+    fn item_that_cannot_be_walked_to_fails_the_errand() {
+        let mut app = errand_test_app();
+        app.init_resource::<Stockpile>()
+            .add_systems(Update, (designate_collectables, execute_carry_to_depot))
+            .add_errand::<CarryToDepotErrand>()
+            .add_errand::<MoveToPosition>();
+        app.record_events::<ErrandFailed>();
+
+        let hauler = app.spawn_worker(Vec3::ZERO);
+        app.world.entity_mut(hauler).insert(Hauler);
+        app.world.spawn((
+            Collectable,
+            ResourceItem {
+                resource_type: ResourceType::Ore,
+            },
+            TransformBundle::from_transform(Transform::from_xyz(30.0, 0.0, 0.0)),
+        ));
+        app.step(5);
+
+        // The move ends without getting the hauler anywhere, like a path that leads nowhere.
+        app.finish_errand::<MoveToPosition>(hauler);
+        app.step(5);
+
+        let failed = app.recorded::<ErrandFailed>();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].reason, ErrandFailureReason::PathNotFound);
+        assert!(app.queued(hauler).is_empty(), "shouldn't keep walking to the item");
+    }
+}
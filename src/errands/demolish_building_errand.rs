@@ -0,0 +1,239 @@
+use crate::errands::move_to_position_errand::Approaching;
+use crate::errands::{
+    Builder, Designation, ErrandFailureReason, ErrandTarget, ErrandsV2AppExtensions,
+    MoveToPosition, QueuedErrand, QueuedErrandFailureBuilder, QueuedErrandImpl, SavedErrand,
+    WorkingOnErrand,
+};
+use crate::game_level::{ResourceType, TILE_SIZE};
+use crate::game_level_render::SpawnResources;
+use crate::gizmos::GizmoVisibility;
+use crate::health::{Health, OnDeathAction};
+use crate::prelude::*;
+use crate::resource_items::ResourceModels;
+use bevy::math::Vec3Swizzles;
+
+pub struct DemolishBuildingErrandPlugin;
+
+impl Plugin for DemolishBuildingErrandPlugin {
+    fn build(&self, app: &mut App) {
+        app.load_assets::<DemolishAssets>()
+            .add_systems(
+                Update,
+                (
+                    execute_demolish_building,
+                    add_demolition_refund.run_if(resource_exists::<ResourceModels>()),
+                ),
+            )
+            .add_errand::<DemolishBuildingErrand>()
+            .add_designation_gizmo::<DemolishBuildingGizmo>();
+    }
+}
+
+#[derive(AssetCollection, Resource, Default)]
+pub struct DemolishAssets {
+    #[asset(path = "buildings/demolish.png")]
+    demolish_icon: Handle<Image>,
+}
+
+#[derive(Clone, Debug)]
+pub struct DemolishBuildingErrand {
+    target: Entity,
+}
+
+impl DemolishBuildingErrand {
+    pub fn new(target: Entity) -> Self {
+        Self { target }
+    }
+}
+
+impl Errand for DemolishBuildingErrand {
+    type WorkerComponent = Builder;
+
+    fn on_enqueued<TEnqueued: QueuedErrand>(&self, queued: &mut TEnqueued) {
+        queued.fail_if_entity_missing(self.target);
+    }
+
+    fn get_errand_type_order() -> i32 {
+        7500
+    }
+
+    fn name() -> &'static str {
+        "Demolish"
+    }
+
+    fn target(&self) -> Option<ErrandTarget<Entity>> {
+        Some(ErrandTarget::Entity(self.target))
+    }
+
+    fn save(&self) -> SavedErrand<Entity> {
+        SavedErrand::DemolishBuilding {
+            target: self.target,
+        }
+    }
+}
+
+/// A building that can be torn down, dropping `refund` as collectable resources.
+#[derive(Component)]
+pub struct Demolishable {
+    refund: Vec<(ResourceType, u32)>,
+}
+
+impl Demolishable {
+    pub fn new(cost: &[(ResourceType, u32)]) -> Self {
+        Self {
+            refund: demolition_refund(cost),
+        }
+    }
+}
+
+/// Half of the building cost, rounded down.
+fn demolition_refund(cost: &[(ResourceType, u32)]) -> Vec<(ResourceType, u32)> {
+    cost.iter()
+        .map(|(resource_type, amount)| (*resource_type, amount / 2))
+        .filter(|(_, amount)| *amount > 0)
+        .collect()
+}
+
+fn add_demolition_refund(
+    buildings: Query<(Entity, &Demolishable), Added<Demolishable>>,
+    resource_models: Res<ResourceModels>,
+    mut commands: Commands,
+) {
+    for (entity, demolishable) in buildings.iter() {
+        commands
+            .entity(entity)
+            .insert(OnDeathAction::new(SpawnResources::new(
+                resource_models.clone(),
+                demolishable.refund.clone(),
+            )));
+    }
+}
+
+fn execute_demolish_building(
+    mut workers: Query<(
+        Entity,
+        &mut WorkingOnErrand<DemolishBuildingErrand>,
+        &GlobalTransform,
+        &mut ErrandQueue,
+        Option<&Approaching>,
+    )>,
+    mut buildings: Query<(&mut Health, &GlobalTransform), With<Demolishable>>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (worker, mut errand, worker_position, mut queue, approaching) in workers.iter_mut() {
+        let Ok((mut building, building_position)) = buildings.get_mut(errand.target) else {
+            info!("Building to demolish no longer exists. Removing errand.");
+            errand.done();
+            continue;
+        };
+
+        let building_position = building_position.translation();
+        let distance = (building_position - worker_position.translation())
+            .xz()
+            .length();
+        if distance > TILE_SIZE {
+            if approaching.is_some_and(|a| a.is_for(errand.id(), building_position)) {
+                info!("Could not get to the building. Failing errand.");
+                errand.fail(ErrandFailureReason::PathNotFound);
+                continue;
+            }
+
+            commands
+                .entity(worker)
+                .insert(Approaching::new(errand.id(), building_position));
+            queue.prepend_errand(|id| {
+                let mut e = QueuedErrandImpl::new(
+                    id,
+                    MoveToPosition::new(building_position, Some(TILE_SIZE)),
+                );
+                e.fail_if_entity_missing(errand.target);
+
+                e
+            });
+            continue;
+        }
+
+        if approaching.is_some() {
+            commands.entity(worker).remove::<Approaching>();
+        }
+        building.current -= time.delta_seconds();
+        if building.current <= 0.0 {
+            errand.done();
+            info!("Completed demolish building errand");
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct DemolishBuildingGizmo(ButtonGizmo);
+
+impl HasBaseGizmo for DemolishBuildingGizmo {
+    fn get_base_gizmo(&self) -> &ButtonGizmo {
+        &self.0
+    }
+}
+
+impl GizmoVisibility for DemolishBuildingGizmo {
+    type WorldQuery = Option<&'static Designation>;
+    type ReadOnlyWorldQuery = (With<Selected>, With<Demolishable>);
+
+    fn is_visible(query: &Query<Self::WorldQuery, Self::ReadOnlyWorldQuery>) -> bool {
+        query
+            .iter()
+            .any(|d| !d.is_some_and(|d| d.is_errand::<DemolishBuildingErrand>()))
+    }
+}
+
+impl Gizmo for DemolishBuildingGizmo {
+    type Assets = DemolishAssets;
+
+    fn initialize(assets: &Self::Assets) -> Self {
+        DemolishBuildingGizmo(ButtonGizmo::new(
+            assets.demolish_icon.clone(),
+            "Demolish",
+            0,
+        ))
+    }
+}
+
+impl DesignationGizmo for DemolishBuildingGizmo {
+    type Errand = DemolishBuildingErrand;
+
+    fn create_errand(entity: Entity) -> Self::Errand {
+        DemolishBuildingErrand::new(entity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errands::test_harness::*;
+    use crate::errands::ErrandFailed;
+
+    #[test]
+    fn refunds_half_of_the_cost() {
+        let cost = [(ResourceType::Ore, 5), (ResourceType::Crystal, 1)];
+
+        assert_eq!(demolition_refund(&cost), vec![(ResourceType::Ore, 2)]);
+    }
+
+    #[test]
+    fn queued_demolition_fails_once_the_building_is_gone() {
+        let mut app = errand_test_app();
+        app.record_events::<ErrandFailed>();
+        let worker = app.spawn_worker(Vec3::ZERO);
+        let building = app.spawn_target(Vec3::X);
+        app.queue(worker)
+            .append_independent_errand(DemolishBuildingErrand::new(building));
+        app.step(2);
+
+        app.world.despawn(building);
+        app.step(2);
+
+        assert!(app.queued(worker).is_empty());
+        let failed = app.recorded::<ErrandFailed>();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].reason, ErrandFailureReason::TargetRemoved);
+    }
+}
@@ -7,21 +7,68 @@ use std::fmt::Debug;
 use std::ops::{Deref, DerefMut};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock, TryLockError, Weak};
+use crate::errands::{SavedErrand, SavedQueuedErrand};
+
+/// Where an errand takes the worker.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrandTarget<E> {
+    Position(Vec3),
+    Entity(E),
+}
 
 pub trait Errand: Debug + Clone + Send + Sync + 'static {
     type WorkerComponent: Component;
 
+    /// Called whenever the errand is queued, e.g. to fail it once its target is gone.
     fn on_enqueued<TEnqueued: QueuedErrand>(&self, _queued: &mut TEnqueued) {
         // Default implementation does nothing
     }
 
     fn get_errand_type_order() -> i32;
+
+    /// Shown to the player, e.g. in the priority panel.
+    fn name() -> &'static str;
+
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::ReturnToPool
+    }
+
+    /// How many workers can take a designation of this errand at the same time, unless the
+    /// designation says otherwise, see [`Designation::set_capacity`].
+    fn reservation_capacity(&self) -> usize {
+        1
+    }
+
+    /// Where the errand takes the worker, if anywhere.
+    fn target(&self) -> Option<ErrandTarget<Entity>>;
+
+    fn save(&self) -> SavedErrand<Entity>;
+}
+
+/// What happens when a worker fails the errand it is working on. Errands that fail because
+/// their target is gone or their designation was cancelled are never retried.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RetryPolicy {
+    /// Starts the errand again up to `attempts` times, waiting `backoff` seconds before the
+    /// first retry and twice as long before each following one. Once the attempts run out, the
+    /// errand is returned to the pool.
+    Retry { attempts: u32, backoff: f32 },
+    /// Gives up on the errand and cancels its designation, so nobody else tries either.
+    Skip,
+    /// Releases the designation so another worker can try. The worker that failed leaves it to
+    /// the others for [`FAILED_WORKER_COOLDOWN`] seconds, or until the level changes.
+    ReturnToPool,
 }
 
+/// How long a worker that failed a designation waits before taking it again.
+pub const FAILED_WORKER_COOLDOWN: f32 = 30.0;
+
 #[derive(Component)]
 pub struct ErrandQueue {
     errands: VecDeque<Box<dyn QueuedErrand>>,
     next_id: u64,
+    newly_enqueued: Vec<(u64, &'static str)>,
+    cancelled: Vec<(u64, &'static str)>,
 }
 
 impl ErrandQueue {
@@ -29,6 +76,8 @@ impl ErrandQueue {
         Self {
             errands: VecDeque::new(),
             next_id: 1,
+            newly_enqueued: Vec::new(),
+            cancelled: Vec::new(),
         }
     }
 
@@ -40,11 +89,16 @@ impl ErrandQueue {
 
         info!("Appending errand");
 
+        self.newly_enqueued.push((id, queued_errand.type_name()));
         self.errands.push_back(Box::new(queued_errand));
     }
 
+    /// Independent errands are direct orders, so they are dropped instead of retried when they
+    /// fail.
     pub fn append_independent_errand(&mut self, errand: impl Errand) {
-        self.append_errand(|id| QueuedErrandImpl::new(id, errand));
+        self.append_errand(|id| {
+            QueuedErrandImpl::new(id, errand).with_retry_policy(RetryPolicy::Skip)
+        });
     }
 
     pub fn prepend_errand<T: QueuedErrand>(&mut self, create: impl FnOnce(u64) -> T) {
@@ -53,29 +107,75 @@ impl ErrandQueue {
 
         let queued_errand = create(id);
 
+        self.newly_enqueued.push((id, queued_errand.type_name()));
         self.errands.push_front(Box::new(queued_errand));
     }
 
+    /// Drops every errand, including the one being worked on. They are all reported as
+    /// cancelled, and those enqueued this frame aren't reported as enqueued at all.
     pub fn clear(&mut self) {
-        self.errands.clear();
+        self.newly_enqueued.clear();
+        self.cancelled
+            .extend(self.errands.drain(..).map(|e| (e.id(), e.type_name())));
     }
 
     pub fn len(&self) -> usize {
         self.errands.len()
     }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn QueuedErrand> {
+        self.errands.iter().map(|e| e.as_ref())
+    }
+
+    pub fn front_id(&self) -> Option<u64> {
+        self.errands.front().map(|e| e.id())
+    }
+
+    pub fn contains(&self, id: u64) -> bool {
+        self.errands.iter().any(|e| e.id() == id)
+    }
+
+    pub fn save(&self) -> Vec<SavedQueuedErrand<Entity>> {
+        self.errands.iter().map(|e| e.save()).collect()
+    }
 }
 
 pub trait QueuedErrand: Send + Sync + 'static {
     fn id(&self) -> u64;
+    fn type_name(&self) -> &'static str;
+    fn name(&self) -> &'static str;
+    fn errand_type_id(&self) -> TypeId;
+    fn target(&self) -> Option<ErrandTarget<Entity>>;
     fn activate(&self, commands: &mut EntityCommands);
     fn deactivate(&self, commands: &mut EntityCommands);
     fn fail_on(&self) -> &Vec<FailureCondition>;
     fn add_failure_condition(&mut self, condition: FailureCondition);
+    fn retry_policy(&self) -> RetryPolicy;
+    fn failed_attempts(&self) -> u32;
+    fn retry_at(&mut self, time: f32);
+    fn is_ready(&self, time: f32) -> bool;
+    fn designation(&self) -> Option<Entity>;
+    /// Releases the designation. The worker won't take it again before `until`.
+    fn return_to_pool(&self, until: f32);
+    fn save(&self) -> SavedQueuedErrand<Entity>;
+}
+
+impl dyn QueuedErrand {
+    pub fn is_errand<E: Errand>(&self) -> bool {
+        self.errand_type_id() == TypeId::of::<E>()
+    }
 }
 
 pub trait QueuedErrandFailureBuilder: QueuedErrand {
     fn fail_if_entity_missing(&mut self, entity: Entity) {
-        self.add_failure_condition(FailureCondition::TargetRemoved(entity));
+        let known = self
+            .fail_on()
+            .iter()
+            .any(|c| matches!(c, FailureCondition::TargetRemoved(e) if *e == entity));
+
+        if !known {
+            self.add_failure_condition(FailureCondition::TargetRemoved(entity));
+        }
     }
 
     fn fail_if_cancelled(&mut self, cancelled: Arc<AtomicBool>) {
@@ -90,16 +190,30 @@ pub struct QueuedErrandImpl<T: Errand> {
     errand: T,
     reservation: Option<Arc<ReservedErrand>>,
     fail_on: Vec<FailureCondition>,
+    retry_policy: RetryPolicy,
+    failed_attempts: u32,
+    retry_at: f32,
 }
 
 impl<T: Errand> QueuedErrandImpl<T> {
     pub fn new(id: u64, errand: T) -> Self {
-        Self {
+        let mut queued = Self {
             id,
-            errand,
+            retry_policy: errand.retry_policy(),
+            errand: errand.clone(),
             reservation: None,
             fail_on: Vec::new(),
-        }
+            failed_attempts: 0,
+            retry_at: 0.0,
+        };
+        errand.on_enqueued(&mut queued);
+
+        queued
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
     }
 }
 
@@ -108,13 +222,29 @@ impl<T: Errand> QueuedErrand for QueuedErrandImpl<T> {
         self.id
     }
 
+    fn type_name(&self) -> &'static str {
+        errand_type_name::<T>()
+    }
+
+    fn name(&self) -> &'static str {
+        T::name()
+    }
+
+    fn errand_type_id(&self) -> TypeId {
+        TypeId::of::<T>()
+    }
+
+    fn target(&self) -> Option<ErrandTarget<Entity>> {
+        self.errand.target()
+    }
+
     fn activate(&self, commands: &mut EntityCommands) {
         let work = WorkingOnErrand {
             id: self.id,
             errand: self.errand.clone(),
-            _reservation: self.reservation.clone(),
+            reservation: self.reservation.clone(),
             is_done: false,
-            failed: false,
+            failure: None,
         };
 
         commands.insert(work);
@@ -131,42 +261,105 @@ impl<T: Errand> QueuedErrand for QueuedErrandImpl<T> {
     fn add_failure_condition(&mut self, condition: FailureCondition) {
         self.fail_on.push(condition);
     }
+
+    fn retry_policy(&self) -> RetryPolicy {
+        self.retry_policy
+    }
+
+    fn failed_attempts(&self) -> u32 {
+        self.failed_attempts
+    }
+
+    fn retry_at(&mut self, time: f32) {
+        self.failed_attempts += 1;
+        self.retry_at = time;
+    }
+
+    fn is_ready(&self, time: f32) -> bool {
+        self.retry_at <= time
+    }
+
+    fn designation(&self) -> Option<Entity> {
+        self.reservation.as_ref().map(|r| r.designation)
+    }
+
+    fn return_to_pool(&self, until: f32) {
+        if let Some(reservation) = &self.reservation {
+            reservation.return_to_pool(until);
+        }
+    }
+
+    fn save(&self) -> SavedQueuedErrand<Entity> {
+        SavedQueuedErrand {
+            errand: self.errand.save(),
+            designation: self.reservation.as_ref().map(|r| r.designation),
+            fail_if_missing: self
+                .fail_on
+                .iter()
+                .filter_map(|c| match c {
+                    FailureCondition::TargetRemoved(entity) => Some(*entity),
+                    FailureCondition::TargetErrandCancelled(_) => None,
+                })
+                .collect(),
+        }
+    }
 }
 
 #[derive(Debug)]
 struct AvailableErrand {
     entity: Entity,
+    capacity: usize,
     work_info: Arc<RwLock<AvailableErrandWorkInfo>>,
     factory: Box<dyn ErrandFromAvailableErrand>,
 }
 
 #[derive(Debug, Default)]
 struct AvailableErrandWorkInfo {
-    reserved_by: Weak<ReservedErrand>,
+    reserved_by: Vec<Weak<ReservedErrand>>,
     on_cancel: Vec<Weak<AtomicBool>>,
+    /// Workers that failed the errand, and until when they leave it to others.
+    failed_by: Vec<(Entity, f32)>,
 }
 
 impl AvailableErrand {
     fn new<T: Errand>(entity: Entity, errand: T) -> Self {
         Self {
             entity,
+            capacity: errand.reservation_capacity(),
             work_info: default(),
             factory: Box::new(ErrandFromAvailableErrandImpl { value: errand }),
         }
     }
 
-    fn enqueue(&self, worker: Entity, queue: &mut ErrandQueue) -> bool {
+    fn enqueue(&self, worker: Entity, queue: &mut ErrandQueue, time: f32) -> bool {
         match self.work_info.try_write() {
             Ok(mut lock) => {
-                if lock.reserved_by.strong_count() != 0 {
+                lock.reserved_by.retain(|r| r.strong_count() != 0);
+
+                let already_reserved = lock
+                    .reserved_by
+                    .iter()
+                    .filter_map(|r| r.upgrade())
+                    .any(|r| r.reserved_by == worker);
+
+                let recently_failed = lock
+                    .failed_by
+                    .iter()
+                    .any(|(failed, until)| *failed == worker && *until > time);
+
+                if lock.reserved_by.len() >= self.capacity || already_reserved || recently_failed {
                     return false;
                 }
 
+                lock.failed_by.retain(|(_, until)| *until > time);
+
                 let reservation = Arc::new(ReservedErrand {
                     reserved_by: worker,
+                    designation: self.entity,
+                    work_info: Arc::downgrade(&self.work_info),
                 });
 
-                lock.reserved_by = Arc::downgrade(&reservation);
+                lock.reserved_by.push(Arc::downgrade(&reservation));
 
                 let cancelled = Arc::new(AtomicBool::new(false));
 
@@ -193,6 +386,13 @@ impl AvailableErrand {
     fn errand_type_id(&self) -> TypeId {
         self.factory.errand_type_id()
     }
+
+    fn forget_failures(&self) {
+        match self.work_info.write() {
+            Ok(mut lock) => lock.failed_by.clear(),
+            Err(e) => error!("Poisoned lock: {:?}", e),
+        };
+    }
 }
 
 impl Drop for AvailableErrand {
@@ -200,7 +400,7 @@ impl Drop for AvailableErrand {
         if let Ok(mut lock) = self.work_info.write() {
             for cancel in lock.on_cancel.drain(..) {
                 if let Some(cancel) = cancel.upgrade() {
-                    cancel.store(false, Ordering::Relaxed);
+                    cancel.store(true, Ordering::Relaxed);
                 }
             }
         } else {
@@ -211,6 +411,33 @@ impl Drop for AvailableErrand {
 
 struct ReservedErrand {
     reserved_by: Entity,
+    designation: Entity,
+    work_info: Weak<RwLock<AvailableErrandWorkInfo>>,
+}
+
+impl ReservedErrand {
+    fn return_to_pool(&self, until: f32) {
+        let Some(work_info) = self.work_info.upgrade() else {
+            return;
+        };
+
+        match work_info.write() {
+            Ok(mut lock) => lock.failed_by.push((self.reserved_by, until)),
+            Err(e) => error!("Poisoned lock: {:?}", e),
+        };
+    }
+
+    /// Someone managed to do the errand, so the workers that failed it may try again.
+    fn succeeded(&self) {
+        let Some(work_info) = self.work_info.upgrade() else {
+            return;
+        };
+
+        match work_info.write() {
+            Ok(mut lock) => lock.failed_by.clear(),
+            Err(e) => error!("Poisoned lock: {:?}", e),
+        };
+    }
 }
 
 trait ErrandFromAvailableErrand: Debug + Send + Sync + 'static {
@@ -222,6 +449,8 @@ trait ErrandFromAvailableErrand: Debug + Send + Sync + 'static {
     );
 
     fn errand_type_id(&self) -> TypeId;
+    fn name(&self) -> &'static str;
+    fn save(&self) -> SavedErrand<Entity>;
 }
 
 #[derive(Debug, Clone)]
@@ -237,25 +466,32 @@ impl<T: Errand> ErrandFromAvailableErrand for ErrandFromAvailableErrandImpl<T> {
         queue: &mut ErrandQueue,
     ) {
         queue.append_errand(move |errand_id| QueuedErrandImpl {
-            id: errand_id,
-            errand: self.value.clone(),
             reservation: Some(reservation),
             fail_on,
+            ..QueuedErrandImpl::new(errand_id, self.value.clone())
         });
     }
 
     fn errand_type_id(&self) -> TypeId {
         TypeId::of::<T>()
     }
+
+    fn name(&self) -> &'static str {
+        T::name()
+    }
+
+    fn save(&self) -> SavedErrand<Entity> {
+        self.value.save()
+    }
 }
 
 #[derive(Component, Clone)]
 pub struct WorkingOnErrand<T: Errand> {
     id: u64,
     errand: T,
-    _reservation: Option<Arc<ReservedErrand>>,
+    reservation: Option<Arc<ReservedErrand>>,
     is_done: bool,
-    failed: bool,
+    failure: Option<ErrandFailureReason>,
 }
 
 #[derive(Debug, Clone)]
@@ -265,12 +501,20 @@ pub enum FailureCondition {
 }
 
 impl<T: Errand> WorkingOnErrand<T> {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.is_done
+    }
+
     pub fn done(&mut self) {
         self.is_done = true;
     }
 
-    pub fn fail(&mut self) {
-        self.failed = true;
+    pub fn fail(&mut self, reason: ErrandFailureReason) {
+        self.failure = Some(reason);
     }
 }
 
@@ -300,8 +544,15 @@ impl Designation {
         }
     }
 
-    pub fn enqueue(&self, worker: Entity, queue: &mut ErrandQueue) -> bool {
-        self.errand.enqueue(worker, queue)
+    /// `time` is the elapsed time, used to tell whether the worker failed this errand recently.
+    pub fn enqueue(&self, worker: Entity, queue: &mut ErrandQueue, time: f32) -> bool {
+        self.errand.enqueue(worker, queue, time)
+    }
+
+    /// Lets every worker that failed the errand take it again, e.g. because the level changed
+    /// and they might be able to reach it now.
+    pub fn forget_failures(&self) {
+        self.errand.forget_failures()
     }
 
     fn errand_type_id(&self) -> TypeId {
@@ -311,40 +562,239 @@ impl Designation {
     pub fn is_errand<E: Errand>(&self) -> bool {
         self.errand_type_id() == TypeId::of::<E>()
     }
+
+    pub fn capacity(&self) -> usize {
+        self.errand.capacity
+    }
+
+    /// Overrides how many workers can take the errand at the same time. Workers that already
+    /// took it keep it.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.errand.capacity = capacity;
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.errand.factory.name()
+    }
+
+    pub fn save(&self) -> SavedErrand<Entity> {
+        self.errand.factory.save()
+    }
 }
 
 #[derive(Component)]
-struct IsWorking;
+pub struct IsWorking;
+
+/// The errand type without its module path, e.g. `MineWallErrand`.
+pub fn errand_type_name<T: Errand>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct ErrandEnqueued {
+    pub worker: Entity,
+    pub errand_id: u64,
+    pub errand_type: &'static str,
+}
+
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct ErrandStarted {
+    pub worker: Entity,
+    pub errand_id: u64,
+    pub errand_type: &'static str,
+}
+
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct ErrandCompleted {
+    pub worker: Entity,
+    pub errand_id: u64,
+    pub errand_type: &'static str,
+}
+
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct ErrandFailed {
+    pub worker: Entity,
+    pub errand_id: u64,
+    pub errand_type: &'static str,
+    pub reason: ErrandFailureReason,
+}
+
+/// Sent instead of [`ErrandFailed`] when a failed errand will be started again at `retry_at`,
+/// see [`RetryPolicy::Retry`]. `attempt` counts the failed attempts so far.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct ErrandRetrying {
+    pub worker: Entity,
+    pub errand_id: u64,
+    pub errand_type: &'static str,
+    pub reason: ErrandFailureReason,
+    pub attempt: u32,
+    pub retry_at: f32,
+}
+
+/// Sent when another errand was put in front of the one being worked on. The preempted errand
+/// stays in the queue and is started again once it is back at the front.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct ErrandPreempted {
+    pub worker: Entity,
+    pub errand_id: u64,
+    pub errand_type: &'static str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrandFailureReason {
+    TargetRemoved,
+    Cancelled,
+    PathNotFound,
+    /// The worker can't get to the target from where it is. The designation is left to others
+    /// without a cooldown, and the worker takes it again as soon as the level changes.
+    Unreachable,
+    /// There is no depot to fetch materials from.
+    NoDepot,
+}
+
+fn send_enqueued_events(
+    mut queues: Query<(Entity, &mut ErrandQueue), Changed<ErrandQueue>>,
+    mut events: EventWriter<ErrandEnqueued>,
+) {
+    for (worker, mut queue) in queues.iter_mut() {
+        if queue.newly_enqueued.is_empty() {
+            continue;
+        }
+
+        let newly_enqueued = std::mem::take(&mut queue.bypass_change_detection().newly_enqueued);
+        events.send_batch(
+            newly_enqueued
+                .into_iter()
+                .map(|(errand_id, errand_type)| ErrandEnqueued {
+                    worker,
+                    errand_id,
+                    errand_type,
+                }),
+        );
+    }
+}
+
+fn send_cancelled_events(
+    mut queues: Query<(Entity, &mut ErrandQueue), Changed<ErrandQueue>>,
+    mut events: EventWriter<ErrandFailed>,
+) {
+    for (worker, mut queue) in queues.iter_mut() {
+        if queue.cancelled.is_empty() {
+            continue;
+        }
+
+        let cancelled = std::mem::take(&mut queue.bypass_change_detection().cancelled);
+        events.send_batch(
+            cancelled
+                .into_iter()
+                .map(|(errand_id, errand_type)| ErrandFailed {
+                    worker,
+                    errand_id,
+                    errand_type,
+                    reason: ErrandFailureReason::Cancelled,
+                }),
+        );
+    }
+}
 
 fn clear_finished_errands<T: Errand>(
     mut q: Query<(Entity, &WorkingOnErrand<T>, &mut ErrandQueue)>,
     mut commands: Commands,
+    time: Res<Time>,
+    mut completed: EventWriter<ErrandCompleted>,
+    mut failed: EventWriter<ErrandFailed>,
+    mut retrying: EventWriter<ErrandRetrying>,
 ) {
     for (entity, working_on_errand, mut queue) in q.iter_mut() {
-        if working_on_errand.is_done {
-            info!("Errand {:?} done", working_on_errand.id);
+        let errand_id = working_on_errand.id();
+
+        if let Some(reason) = working_on_errand.failure {
             commands.entity(entity).remove::<WorkingOnErrand<T>>();
-            let first = queue.errands.front();
-            if let Some(first) = first {
-                if first.id() == working_on_errand.id {
-                    queue.errands.pop_front();
+
+            let Some(index) = queue.errands.iter().position(|e| e.id() == errand_id) else {
+                continue;
+            };
+            let queued = &mut queue.errands[index];
+
+            // Unreachable designations wait for the level to change instead of a cooldown.
+            let unreachable =
+                reason == ErrandFailureReason::Unreachable && queued.designation().is_some();
+
+            match queued.retry_policy() {
+                _ if unreachable => queued.return_to_pool(f32::INFINITY),
+                RetryPolicy::Retry { attempts, backoff } if queued.failed_attempts() < attempts => {
+                    let delay = backoff * 2f32.powi(queued.failed_attempts() as i32);
+                    info!("Errand {:?} failed: {:?}, retrying in {}s", errand_id, reason, delay);
+                    let retry_at = time.elapsed_seconds() + delay;
+                    queued.retry_at(retry_at);
+                    retrying.send(ErrandRetrying {
+                        worker: entity,
+                        errand_id,
+                        errand_type: errand_type_name::<T>(),
+                        reason,
+                        attempt: queued.failed_attempts(),
+                        retry_at,
+                    });
+                    continue;
                 }
+                RetryPolicy::Skip => {
+                    if let Some(designation) = queued.designation() {
+                        if let Some(mut designation) = commands.get_entity(designation) {
+                            designation.remove::<Designation>();
+                        }
+                    }
+                }
+                RetryPolicy::Retry { .. } | RetryPolicy::ReturnToPool => {
+                    queued.return_to_pool(time.elapsed_seconds() + FAILED_WORKER_COOLDOWN)
+                }
+            }
+
+            info!("Errand {:?} failed: {:?}", errand_id, reason);
+            failed.send(ErrandFailed {
+                worker: entity,
+                errand_id,
+                errand_type: errand_type_name::<T>(),
+                reason,
+            });
+            queue.errands.remove(index);
+            continue;
+        } else if working_on_errand.is_done() {
+            info!("Errand {:?} done", errand_id);
+            if let Some(reservation) = &working_on_errand.reservation {
+                reservation.succeeded();
             }
+            completed.send(ErrandCompleted {
+                worker: entity,
+                errand_id,
+                errand_type: errand_type_name::<T>(),
+            });
+        } else {
+            continue;
+        }
+
+        commands.entity(entity).remove::<WorkingOnErrand<T>>();
+        if queue.front_id() == Some(errand_id) {
+            queue.errands.pop_front();
         }
     }
 }
 
-fn check_failed_errands(mut q: Query<&mut ErrandQueue>, errand_targets: Query<()>) {
-    for mut queue in q.iter_mut() {
+fn check_failed_errands(
+    mut q: Query<(Entity, &mut ErrandQueue)>,
+    errand_targets: Query<()>,
+    mut failed: EventWriter<ErrandFailed>,
+) {
+    for (worker, mut queue) in q.iter_mut() {
         queue.errands.retain(|e| {
             for failure_condition in e.fail_on() {
-                let retain = match failure_condition {
+                let reason = match failure_condition {
                     FailureCondition::TargetRemoved(target) =>{
                         if !errand_targets.contains(*target) {
                             info!("Errand target removed");
-                            false
+                            Some(ErrandFailureReason::TargetRemoved)
                         } else {
-                            true
+                            None
                         }
 
 
@@ -352,15 +802,21 @@ fn check_failed_errands(mut q: Query<&mut ErrandQueue>, errand_targets: Query<()
                     FailureCondition::TargetErrandCancelled(cancelled) => {
                         if cancelled.load(Ordering::Relaxed) {
                             info!("Errand explicitly cancelled");
-                            false
+                            Some(ErrandFailureReason::Cancelled)
                         } else {
-                            true
+                            None
                         }
                     }
                 };
 
-                if !retain {
+                if let Some(reason) = reason {
                     info!("Errand {:?} failed", e.id());
+                    failed.send(ErrandFailed {
+                        worker,
+                        errand_id: e.id(),
+                        errand_type: e.type_name(),
+                        reason,
+                    });
                     return false;
                 }
             }
@@ -373,14 +829,23 @@ fn check_failed_errands(mut q: Query<&mut ErrandQueue>, errand_targets: Query<()
 fn cancel_current_task_when_overwritten<T: Errand>(
     q: Query<(&ErrandQueue, &WorkingOnErrand<T>, Entity)>,
     mut commands: Commands,
+    mut preempted: EventWriter<ErrandPreempted>,
 ) {
     for (queue, errand, entity) in q.iter() {
-        let first = queue.errands.front();
-
-        if let Some(first) = first {
-            if first.id() != errand.id {
-                info!("Errand {:?} overwritten, removing", errand.id);
+        if let Some(first) = queue.front_id() {
+            if first != errand.id() {
+                info!("Errand {:?} overwritten, removing", errand.id());
                 commands.entity(entity).remove::<WorkingOnErrand<T>>();
+
+                // Errands that were cleared from the queue are reported as cancelled by
+                // `send_cancelled_events`.
+                if queue.contains(errand.id()) {
+                    preempted.send(ErrandPreempted {
+                        worker: entity,
+                        errand_id: errand.id(),
+                        errand_type: errand_type_name::<T>(),
+                    });
+                }
             }
         } else {
             info!("Working on errand but queue is empty");
@@ -396,17 +861,27 @@ fn has_workers<T: Errand>(q: Query<&WorkingOnErrand<T>>) -> bool {
 fn start_next_task<T: Errand>(
     q: Query<&ErrandQueue>,
     mut commands: Commands,
+    time: Res<Time>,
     mut finished_current: RemovedComponents<WorkingOnErrand<T>>,
+    mut started: EventWriter<ErrandStarted>,
 ) {
     for entity in finished_current.iter() {
         if let Ok(queue) = q.get(entity) {
-            let first = queue.errands.front();
+            let first = queue
+                .errands
+                .front()
+                .filter(|e| e.is_ready(time.elapsed_seconds()));
 
             let mut entity_commands = commands.entity(entity);
             if let Some(first) = first {
                 info!("Activating next errand");
                 first.activate(&mut entity_commands);
                 entity_commands.insert(IsWorking);
+                started.send(ErrandStarted {
+                    worker: entity,
+                    errand_id: first.id(),
+                    errand_type: first.type_name(),
+                });
             } else {
                 entity_commands.remove::<IsWorking>();
             }
@@ -421,15 +896,25 @@ fn has_finished_work<T: Errand>(finished: RemovedComponents<WorkingOnErrand<T>>)
 fn start_next_errand_in_queue(
     workers: Query<(Entity, &ErrandQueue), Without<IsWorking>>,
     mut commands: Commands,
+    time: Res<Time>,
+    mut started: EventWriter<ErrandStarted>,
 ) {
     for (entity, queue) in workers.iter() {
-        let first = queue.errands.front();
+        let first = queue
+            .errands
+            .front()
+            .filter(|e| e.is_ready(time.elapsed_seconds()));
 
         if let Some(first) = first {
             info!("Starting next errand in queue");
             let mut entity_commands = commands.entity(entity);
             first.activate(&mut entity_commands);
             entity_commands.insert(IsWorking);
+            started.send(ErrandStarted {
+                worker: entity,
+                errand_id: first.id(),
+                errand_type: first.type_name(),
+            });
         }
     }
 }
@@ -438,7 +923,14 @@ pub struct ErrandsV2Plugin;
 
 impl Plugin for ErrandsV2Plugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (check_failed_errands, assign_available_errands, start_next_errand_in_queue));
+        app.add_event::<ErrandEnqueued>()
+            .add_event::<ErrandStarted>()
+            .add_event::<ErrandCompleted>()
+            .add_event::<ErrandFailed>()
+            .add_event::<ErrandRetrying>()
+            .add_event::<ErrandPreempted>()
+            .add_systems(Update, (check_failed_errands, assign_available_errands, start_next_errand_in_queue))
+            .add_systems(PostUpdate, (send_enqueued_events, send_cancelled_events).chain());
     }
 }
 
@@ -491,6 +983,7 @@ fn has_removed<C: Component>(q: RemovedComponents<C>) -> bool {
 
 fn assign_available_errands(
     designations: Query<(&Designation, &GlobalTransform)>,
+    time: Res<Time>,
     mut workers: Query<
         (
             Entity,
@@ -501,6 +994,8 @@ fn assign_available_errands(
         Without<IsWorking>,
     >,
 ) {
+    let time = time.elapsed_seconds();
+
     workers
         .par_iter_mut()
         .for_each_mut(|(entity, mut queue, worker_transform, priorities)| {
@@ -533,7 +1028,7 @@ fn assign_available_errands(
                     .map(|(des, _)| des);
 
                 for designation in available_designations {
-                    if designation.enqueue(entity, &mut queue) {
+                    if designation.enqueue(entity, &mut queue, time) {
                         info!("Found errand for worker: {:?}", entity);
                         return;
                     }
@@ -542,12 +1037,33 @@ fn assign_available_errands(
         })
 }
 
+/// Which errand types a worker takes from designations, and in what order. Lower priorities
+/// are taken first, and errand types without a priority are never taken.
 #[derive(Component, Default)]
 pub struct WorkerPriorities {
     priorities: Vec<WorkerPriority>,
 }
 
 impl WorkerPriorities {
+    pub const DEFAULT: u8 = 3;
+    pub const LOWEST: u8 = 5;
+
+    /// The errand types the worker is capable of, in errand type order.
+    pub fn capabilities(&self) -> impl Iterator<Item = &WorkerPriority> {
+        self.priorities.iter().filter(|p| p.available)
+    }
+
+    /// Sets the priority for the errand type, or disables it with `None`.
+    pub fn set_priority(&mut self, errand_type_id: TypeId, priority: Option<u8>) {
+        let priority = priority.map(|p| p.clamp(1, Self::LOWEST));
+
+        for p in self.priorities.iter_mut() {
+            if p.errand_type_id == errand_type_id {
+                p.priority = priority;
+            }
+        }
+    }
+
     fn add_capability<E: Errand>(&mut self) {
         let id = TypeId::of::<E>();
         let priority = self.priorities.iter_mut().find(|p| p.errand_type_id == id);
@@ -555,14 +1071,13 @@ impl WorkerPriorities {
         if let Some(priority) = priority {
             priority.available = true;
         } else {
-            let new_priority = WorkerPriority {
-                priority: Some(5),
-                available: true,
+            self.priorities.push(WorkerPriority {
+                priority: Some(Self::DEFAULT),
                 errand_type_id: id,
+                available: true,
                 errand_type_order: E::get_errand_type_order(),
-            };
-
-            self.priorities.push(new_priority);
+                name: E::name(),
+            });
             self.priorities.sort_by_key(|p| p.errand_type_order);
         }
     }
@@ -582,4 +1097,23 @@ pub struct WorkerPriority {
     errand_type_id: TypeId,
     available: bool,
     errand_type_order: i32,
+    name: &'static str,
+}
+
+impl WorkerPriority {
+    pub fn priority(&self) -> Option<u8> {
+        self.priority
+    }
+
+    pub fn errand_type_id(&self) -> TypeId {
+        self.errand_type_id
+    }
+
+    pub fn errand_type_order(&self) -> i32 {
+        self.errand_type_order
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
 }
@@ -1,9 +1,10 @@
 use crate::errands::{
-    Designation, ErrandsV2AppExtensions, MoveToPosition, QueuedErrand, QueuedErrandFailureBuilder,
-    QueuedErrandImpl, WorkingOnErrand,
+    Designation, ErrandFailureReason, ErrandTarget, ErrandsV2AppExtensions, MoveToPosition,
+    QueuedErrand, QueuedErrandFailureBuilder, QueuedErrandImpl, SavedErrand, WorkingOnErrand,
 };
-use crate::game_level::TILE_SIZE;
+use crate::game_level::{GameLevel, HALF_TILE_SIZE, TILE_SIZE};
 use crate::gizmos::GizmoVisibility;
+use crate::grid::GridPosition;
 use crate::health::Health;
 use crate::prelude::*;
 use crate::MyAssets;
@@ -12,9 +13,18 @@ pub struct MineWallErrandPlugin;
 
 impl Plugin for MineWallErrandPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (execute_mine_wall, start_mining_wall))
-            .add_errand::<MineWallErrand>()
-            .add_designation_gizmo::<MineWallGizmo>();
+        app.add_systems(
+            Update,
+            (
+                execute_mine_wall.run_if(resource_exists::<GameLevel>()),
+                release_abandoned_sides,
+                start_mining_wall,
+                limit_miners_to_open_sides.run_if(resource_exists::<GameLevel>()),
+            ),
+        )
+        .add_errand::<MineWallErrand>()
+        .add_designation_gizmo::<MineWallGizmo>()
+        .add_area_designation_gizmo::<MineWallGizmo>();
     }
 }
 
@@ -39,48 +49,215 @@ impl Errand for MineWallErrand {
     fn get_errand_type_order() -> i32 {
         5000
     }
+
+    fn name() -> &'static str {
+        "Mine"
+    }
+
+    fn reservation_capacity(&self) -> usize {
+        SIDES.len()
+    }
+
+    fn target(&self) -> Option<ErrandTarget<Entity>> {
+        Some(ErrandTarget::Entity(self.target))
+    }
+
+    fn save(&self) -> SavedErrand<Entity> {
+        SavedErrand::MineWall {
+            target: self.target,
+        }
+    }
 }
 
 #[derive(Component)]
 pub struct Minable;
 
-fn execute_mine_wall(
+/// Miners working on the same wall spread out over these sides.
+const SIDES: [GridPosition; 4] = [
+    GridPosition { x: 1, z: 0 },
+    GridPosition { x: -1, z: 0 },
+    GridPosition { x: 0, z: 1 },
+    GridPosition { x: 0, z: -1 },
+];
+
+/// How far from the wall center miners stand, leaving some room between them and the face.
+const FACE_DISTANCE: f32 = HALF_TILE_SIZE + 1.5;
+const REACH: f32 = TILE_SIZE / 4.;
+
+/// The tile next to the wall the miner stands on while mining it.
+#[derive(Component, Debug)]
+pub struct MiningFrom {
+    wall: Entity,
+    tile: GridPosition,
+    /// Set while walking to the wall, so a failed approach isn't tried over and over.
+    approaching: bool,
+}
+
+/// The point just in front of the wall face on the side of `tile`.
+fn face_position(level: &GameLevel, wall: GridPosition, tile: GridPosition) -> Vec3 {
+    let direction = Vec3::new((tile.x - wall.x) as f32, 0., (tile.z - wall.z) as f32);
+    level.get_position_at(wall) + direction * FACE_DISTANCE
+}
+
+/// The side of the wall closest to the miner that it can walk to. Sides nobody else is mining
+/// from are preferred, but miners share a side rather than not help at all.
+fn choose_side(
+    level: &GameLevel,
+    wall: GridPosition,
+    miner_position: Vec3,
+    taken: &[GridPosition],
+) -> Option<GridPosition> {
+    let reachable = level.reachable_from(level.get_tile_at(miner_position));
+    let miner_position = miner_position * (Vec3::X + Vec3::Z);
+
+    SIDES
+        .iter()
+        .map(|side| GridPosition::new(wall.x + side.x, wall.z + side.z))
+        .filter(|tile| reachable.contains(tile))
+        .map(|tile| {
+            let distance = face_position(level, wall, tile).distance_squared(miner_position);
+            (tile, taken.contains(&tile), distance)
+        })
+        .min_by(|(_, a_taken, a), (_, b_taken, b)| a_taken.cmp(b_taken).then(a.total_cmp(b)))
+        .map(|(tile, ..)| tile)
+}
+
+pub fn execute_mine_wall(
     mut miners: Query<(
+        Entity,
         &mut WorkingOnErrand<MineWallErrand>,
         &GlobalTransform,
         &mut ErrandQueue,
+        Option<&MiningFrom>,
     )>,
+    claims: Query<(Entity, &MiningFrom)>,
     mut walls: Query<(&mut Health, &GlobalTransform), With<Minable>>,
+    level: Res<GameLevel>,
     time: Res<Time>,
+    mut commands: Commands,
 ) {
-    for (mut errand, miner_position, mut queue) in miners.iter_mut() {
-        if let Ok((mut wall, wall_position)) = walls.get_mut(errand.target) {
-            if wall_position
-                .translation_vec3a()
-                .distance(miner_position.translation_vec3a())
-                > TILE_SIZE
-            {
-                queue.prepend_errand(|id| {
-                    let mut e = QueuedErrandImpl::new(
-                        id,
-                        MoveToPosition::new(wall_position.translation(), None),
-                    );
-                    e.fail_if_entity_missing(errand.target);
-
-                    e
-                });
-                continue;
-            }
+    // Sides claimed this frame are added as well, their components are only inserted later.
+    let mut claimed = claims
+        .iter()
+        .map(|(miner, from)| (miner, from.wall, from.tile))
+        .collect_vec();
 
-            wall.current -= time.delta_seconds();
-            info!("Remaining wall health: {}", wall.current);
-            if wall.current <= 0.0 {
-                errand.done();
-                info!("Completed mine wall errand");
-            }
-        } else {
+    for (miner, mut errand, miner_position, mut queue, mining_from) in miners.iter_mut() {
+        let Ok((mut wall, wall_position)) = walls.get_mut(errand.target) else {
             info!("Target wall to mine no longer exists. Removing errand.");
             errand.done();
+            continue;
+        };
+
+        let wall_tile = level.get_tile_at(wall_position.translation());
+        let mining_from = mining_from.filter(|from| from.wall == errand.target);
+
+        let (side, approaching) = match mining_from {
+            Some(from) => (from.tile, from.approaching),
+            None => {
+                let taken = claimed
+                    .iter()
+                    .filter(|(other, wall, _)| *other != miner && *wall == errand.target)
+                    .map(|(_, _, tile)| *tile)
+                    .collect_vec();
+
+                let Some(side) =
+                    choose_side(&level, wall_tile, miner_position.translation(), &taken)
+                else {
+                    info!("No side of the wall can be reached. Failing errand.");
+                    errand.fail(ErrandFailureReason::Unreachable);
+                    continue;
+                };
+
+                claimed.retain(|(other, ..)| *other != miner);
+                claimed.push((miner, errand.target, side));
+                (side, false)
+            }
+        };
+
+        let stand_at = face_position(&level, wall_tile, side);
+        let out_of_reach =
+            ((stand_at - miner_position.translation()) * (Vec3::X + Vec3::Z)).length() > REACH;
+
+        if out_of_reach && approaching {
+            info!("Could not get to the wall. Failing errand.");
+            errand.fail(ErrandFailureReason::PathNotFound);
+            continue;
+        }
+
+        if mining_from.is_none() || out_of_reach != approaching {
+            commands.entity(miner).insert(MiningFrom {
+                wall: errand.target,
+                tile: side,
+                approaching: out_of_reach,
+            });
+        }
+
+        if out_of_reach {
+            queue.prepend_errand(|id| {
+                let mut e = QueuedErrandImpl::new(id, MoveToPosition::new(stand_at, None));
+                e.fail_if_entity_missing(errand.target);
+
+                e
+            });
+            continue;
+        }
+
+        wall.current -= time.delta_seconds();
+        info!("Remaining wall health: {}", wall.current);
+    }
+
+    // Only checked once everyone has worked on their wall, so all miners of a wall finish
+    // together.
+    for (_, mut errand, ..) in miners.iter_mut() {
+        if walls
+            .get(errand.target)
+            .is_ok_and(|(wall, _)| wall.current <= 0.0)
+        {
+            errand.done();
+            info!("Completed mine wall errand");
+        }
+    }
+}
+
+/// A wall has room for one miner per open side, which changes as the walls around it are
+/// mined.
+fn limit_miners_to_open_sides(
+    mut walls: Query<(&mut Designation, &GlobalTransform), With<Minable>>,
+    level: Res<GameLevel>,
+) {
+    for (mut designation, transform) in walls.iter_mut() {
+        if !designation.is_errand::<MineWallErrand>()
+            || !(designation.is_added() || level.is_changed())
+        {
+            continue;
+        }
+
+        let wall = level.get_tile_at(transform.translation());
+        let open_sides = SIDES
+            .iter()
+            .filter(|side| level.is_open(wall.x + side.x, wall.z + side.z))
+            .count();
+
+        // Walls without an open side keep room for one, so the errand can fail as unreachable.
+        let capacity = open_sides.max(1);
+        if designation.capacity() != capacity {
+            designation.set_capacity(capacity);
+        }
+    }
+}
+
+fn release_abandoned_sides(
+    miners: Query<(Entity, &MiningFrom, &ErrandQueue), Changed<ErrandQueue>>,
+    mut commands: Commands,
+) {
+    for (miner, from, queue) in miners.iter() {
+        let still_mining = queue.iter().any(|e| {
+            e.is_errand::<MineWallErrand>() && e.target() == Some(ErrandTarget::Entity(from.wall))
+        });
+
+        if !still_mining {
+            commands.entity(miner).remove::<MiningFrom>();
         }
     }
 }
@@ -139,3 +316,193 @@ impl DesignationGizmo for MineWallGizmo {
         MineWallErrand::new(entity)
     }
 }
+
+impl AreaDesignationGizmo for MineWallGizmo {
+    type Target = With<Minable>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errands::test_harness::*;
+    use crate::errands::{forget_failures_when_level_changes, ErrandCompleted, ErrandFailed};
+    use crate::grid::Grid;
+    use crate::health::HealthPlugin;
+
+    fn level(rows: &[&str]) -> GameLevel {
+        GameLevel::new_from_open_tiles(Grid::from_rows(rows))
+    }
+
+    #[test]
+    fn chooses_the_closest_side_that_can_be_walked_to() {
+        // The wall is at W. The side to its left is closest, but walled in.
+        let level = level(&["o#ooo", "#oWoo", "o###o", "ooooo"]);
+        let wall = GridPosition::new(2, 1);
+        let miner = level.get_position_at(GridPosition::new(0, 2));
+
+        assert_eq!(
+            choose_side(&level, wall, miner, &[]),
+            Some(GridPosition::new(2, 0))
+        );
+        assert_eq!(
+            choose_side(&level, wall, miner, &[GridPosition::new(2, 0)]),
+            Some(GridPosition::new(3, 1))
+        );
+        assert_eq!(
+            choose_side(
+                &level,
+                wall,
+                miner,
+                &[GridPosition::new(2, 0), GridPosition::new(3, 1)]
+            ),
+            Some(GridPosition::new(2, 0)),
+            "should share a side when all are taken"
+        );
+    }
+
+    #[test]
+    fn diagonal_neighbours_are_not_sides() {
+        let level = level(&["o#o", "#W#", "o#o"]);
+
+        let side = choose_side(
+            &level,
+            GridPosition::new(1, 1),
+            level.get_position_at(GridPosition::new(0, 0)),
+            &[],
+        );
+
+        assert_eq!(side, None);
+    }
+
+    #[test]
+    fn miners_stand_in_front_of_the_face() {
+        let level = level(&["ooo", "oWo", "ooo"]);
+        let wall = GridPosition::new(1, 1);
+
+        let position = face_position(&level, wall, GridPosition::new(1, 2));
+
+        assert_eq!(position.x, level.get_position_at(wall).x);
+        assert!(position.z > level.get_position_at(wall).z + HALF_TILE_SIZE);
+        assert!(position.z < level.get_position_at(GridPosition::new(1, 2)).z);
+    }
+
+    #[test]
+    fn walls_only_take_as_many_miners_as_they_have_open_sides() {
+        let mut app = errand_test_app();
+        app.insert_resource(level(&["ooo", "#W#", "###"]))
+            .add_systems(Update, limit_miners_to_open_sides)
+            .add_errand::<MineWallErrand>();
+
+        let west = app.spawn_worker(Vec3::new(5.0, 0.0, 5.0));
+        let east = app.spawn_worker(Vec3::new(25.0, 0.0, 5.0));
+        app.world.entity_mut(west).insert(Miner);
+        app.world.entity_mut(east).insert(Miner);
+        let wall = app
+            .world
+            .spawn((
+                Minable,
+                TransformBundle::from_transform(Transform::from_xyz(15.0, 5.0, 15.0)),
+            ))
+            .id();
+        app.designate(wall, MineWallErrand::new(wall));
+        app.step(3);
+
+        let mining = [app.queued(west), app.queued(east)]
+            .iter()
+            .filter(|queued| !queued.is_empty())
+            .count();
+        assert_eq!(mining, 1, "only one side is open");
+
+        app.insert_resource(level(&["ooo", "oW#", "o##"]));
+        app.step(3);
+
+        assert!(!app.queued(west).is_empty());
+        assert!(!app.queued(east).is_empty());
+        assert_eq!(app.world.get::<Designation>(wall).unwrap().capacity(), 2);
+    }
+
+    #[test]
+    fn miners_share_a_wall_from_different_sides() {
+        let mut app = errand_test_app();
+        app.insert_resource(GameLevel::new_from_open_tiles(Grid::new(3, 3, true)))
+            .add_plugins(HealthPlugin)
+            .add_systems(Update, execute_mine_wall)
+            .add_errand::<MineWallErrand>();
+        app.record_events::<ErrandCompleted>();
+
+        let west = app.spawn_worker(Vec3::new(8.0, 0.0, 15.0));
+        let east = app.spawn_worker(Vec3::new(22.0, 0.0, 15.0));
+        app.world.entity_mut(west).insert(Miner);
+        app.world.entity_mut(east).insert(Miner);
+        let wall = app
+            .world
+            .spawn((
+                Minable,
+                Health::new(1.0),
+                TransformBundle::from_transform(Transform::from_xyz(15.0, 5.0, 15.0)),
+            ))
+            .id();
+        app.designate(wall, MineWallErrand::new(wall));
+        app.step(3);
+
+        let mining = vec![SavedErrand::MineWall { target: wall }];
+        assert_eq!(app.queued(west), mining, "both should be mining without moving");
+        assert_eq!(app.queued(east), mining, "both should be mining without moving");
+
+        let before = app.world.get::<Health>(wall).unwrap().current;
+        app.step(6);
+        let mined = before - app.world.get::<Health>(wall).unwrap().current;
+        assert!((mined - 12.0 * TEST_TIMESTEP).abs() < 0.001, "mining speed should stack");
+
+        app.step(60);
+
+        assert!(app.world.get_entity(wall).is_none());
+        let completed = app.recorded::<ErrandCompleted>();
+        assert!(completed.iter().any(|e| e.worker == west));
+        assert!(completed.iter().any(|e| e.worker == east));
+    }
+
+    #[test]
+    fn wall_without_a_reachable_side_fails_the_errand() {
+        let mut app = errand_test_app();
+        // Only the corners are open, so the wall can't be reached from any of its sides.
+        let corners = (0..9).map(|i| i % 2 == 0 && i != 4).collect_vec();
+        let level = GameLevel::new_from_open_tiles(Grid::new_from_list(3, 3, corners.clone()));
+        app.insert_resource(level)
+            .add_systems(Update, execute_mine_wall)
+            .add_systems(
+                Update,
+                forget_failures_when_level_changes
+                    .run_if(resource_exists_and_changed::<GameLevel>()),
+            )
+            .add_errand::<MineWallErrand>();
+        app.record_events::<ErrandFailed>();
+
+        let worker = app.spawn_worker(Vec3::new(5.0, 0.0, 5.0));
+        app.world.entity_mut(worker).insert(Miner);
+        let wall = app
+            .world
+            .spawn((
+                Minable,
+                Health::new(1.0),
+                TransformBundle::from_transform(Transform::from_xyz(15.0, 5.0, 15.0)),
+            ))
+            .id();
+        app.designate(wall, MineWallErrand::new(wall));
+        app.step(10);
+
+        let failed = app.recorded::<ErrandFailed>();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].reason, ErrandFailureReason::Unreachable);
+        assert!(app.queued(worker).is_empty(), "shouldn't try to walk into the wall");
+        assert!(app.world.get::<Designation>(wall).is_some());
+
+        let mut open_side = corners;
+        open_side[1] = true;
+        app.insert_resource(GameLevel::new_from_open_tiles(Grid::new_from_list(3, 3, open_side)));
+        app.step(3);
+
+        assert!(app.queued(worker).contains(&SavedErrand::MineWall { target: wall }));
+        assert_eq!(app.recorded::<ErrandFailed>().len(), 1);
+    }
+}
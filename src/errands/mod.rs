@@ -1,15 +1,29 @@
 use crate::prelude::*;
 use crate::errands::move_to_position_errand::MoveToPositionErrandPlugin;
+use crate::game_level::GameLevel;
 
+pub mod build_errand;
+pub mod carry_to_depot_errand;
+pub mod demolish_building_errand;
 pub mod mine_wall_errand;
 pub mod move_to_position_errand;
+mod saved_errand;
 mod sleep_errand;
 mod errands_v2;
+#[cfg(test)]
+pub mod test_harness;
 
+use build_errand::BuildErrandPlugin;
+use carry_to_depot_errand::CarryToDepotErrandPlugin;
+use demolish_building_errand::DemolishBuildingErrandPlugin;
 use mine_wall_errand::MineWallErrandPlugin;
 use sleep_errand::{execute_sleep_errand, SleepErrand};
+pub use build_errand::{BuildErrand, Builder};
+pub use carry_to_depot_errand::{Carrying, Collectable, Hauler};
+pub use demolish_building_errand::Demolishable;
 pub use mine_wall_errand::{Minable, MineWallErrand, Miner};
 pub use move_to_position_errand::{MoveToPosition, Standable, PlayerMovable};
+pub use saved_errand::{SavedErrand, SavedQueuedErrand};
 pub use errands_v2::*;
 
 pub struct ErrandsPlugin;
@@ -18,7 +32,26 @@ impl Plugin for ErrandsPlugin {
     fn build(&self, app: &mut App) {
         app
             .add_systems(Update, execute_sleep_errand)
+            .add_systems(
+                Update,
+                forget_failures_when_level_changes
+                    .run_if(resource_exists_and_changed::<GameLevel>()),
+            )
             .add_errand::<SleepErrand>()
-            .add_plugins((MoveToPositionErrandPlugin, MineWallErrandPlugin, ErrandsV2Plugin));
+            .add_plugins((
+                MoveToPositionErrandPlugin,
+                MineWallErrandPlugin,
+                CarryToDepotErrandPlugin,
+                BuildErrandPlugin,
+                DemolishBuildingErrandPlugin,
+                ErrandsV2Plugin,
+            ));
+    }
+}
+
+/// Mining a wall may open up a path to designations that workers failed to reach before.
+fn forget_failures_when_level_changes(designations: Query<&Designation>) {
+    for designation in designations.iter() {
+        designation.forget_failures();
     }
 }
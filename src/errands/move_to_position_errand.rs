@@ -1,7 +1,10 @@
 use oxidized_navigation::{NavMesh, NavMeshSettings, query::find_path};
 use crate::game_level::GameLevel;
 use crate::prelude::*;
-use crate::errands::{Errand, ErrandQueue, ErrandsV2AppExtensions, WorkingOnErrand};
+use crate::errands::{
+    Errand, ErrandFailureReason, ErrandQueue, ErrandTarget, ErrandsV2AppExtensions, RetryPolicy,
+    SavedErrand, WorkingOnErrand,
+};
 
 #[derive(Clone, Debug)]
 pub struct MoveToPosition {
@@ -18,6 +21,19 @@ impl MoveToPosition {
             search_radius,
         }
     }
+
+    /// The waypoints that are still ahead, empty until the path has been found.
+    pub fn remaining_path(&self) -> &[Vec3] {
+        self.path
+            .as_ref()
+            .map_or(&[], |p| &p.path[p.next.min(p.path.len())..])
+    }
+
+    pub fn progress(&self) -> Option<f32> {
+        self.path
+            .as_ref()
+            .map(|p| p.next as f32 / p.path.len().max(1) as f32)
+    }
 }
 
 impl Errand for MoveToPosition {
@@ -26,6 +42,48 @@ impl Errand for MoveToPosition {
     fn get_errand_type_order() -> i32 {
         0
     }
+
+    fn name() -> &'static str {
+        "Move"
+    }
+
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::Retry {
+            attempts: 2,
+            backoff: 1.0,
+        }
+    }
+
+    fn target(&self) -> Option<ErrandTarget<Entity>> {
+        Some(ErrandTarget::Position(self.target))
+    }
+
+    fn save(&self) -> SavedErrand<Entity> {
+        SavedErrand::MoveToPosition {
+            target: self.target,
+            search_radius: self.search_radius,
+        }
+    }
+}
+
+/// Added to workers an errand sent ahead to `target`, so an approach that didn't get them
+/// there fails the errand instead of being tried over and over.
+#[derive(Component, Debug)]
+pub struct Approaching {
+    errand: u64,
+    target: Vec3,
+}
+
+impl Approaching {
+    pub fn new(errand: u64, target: Vec3) -> Self {
+        Self { errand, target }
+    }
+
+    /// Whether the worker was already sent to `target` by the errand. Targets that moved a
+    /// little, like items rolling on the floor, still count as the same.
+    pub fn is_for(&self, errand: u64, target: Vec3) -> bool {
+        self.errand == errand && self.target.distance_squared(target) < 1.0
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -88,7 +146,7 @@ fn execute_move_to_position(
                             "Failed to find path from {:?} to {:?}. Skipping errand. Error: {:?}",
                             start_pos, errand.target, e
                         );
-                        errand.fail();
+                        errand.fail(ErrandFailureReason::PathNotFound);
                         // commands.entity(entity).remove::<MoveToPosition>();
                         continue;
                     }
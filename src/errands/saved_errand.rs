@@ -0,0 +1,191 @@
+use crate::errands::carry_to_depot_errand::CarryToDepotErrand;
+use crate::errands::demolish_building_errand::DemolishBuildingErrand;
+use crate::errands::sleep_errand::SleepErrand;
+use crate::errands::{
+    BuildErrand, Designation, MineWallErrand, MoveToPosition, QueuedErrandFailureBuilder,
+    QueuedErrandImpl,
+};
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// An errand in a form that can be written to a save file. `E` is how the errand refers to
+/// other entities: plain entities while the game runs, stable references inside a save.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum SavedErrand<E> {
+    MoveToPosition {
+        target: Vec3,
+        search_radius: Option<f32>,
+    },
+    Sleep {
+        duration: f32,
+    },
+    MineWall {
+        target: E,
+    },
+    CarryToDepot {
+        item: E,
+    },
+    Build {
+        site: E,
+    },
+    DemolishBuilding {
+        target: E,
+    },
+}
+
+/// A queued errand along with what it depends on. Errands taken from a designation are
+/// re-reserved from that designation when loaded.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedQueuedErrand<E> {
+    pub errand: SavedErrand<E>,
+    pub designation: Option<E>,
+    pub fail_if_missing: Vec<E>,
+}
+
+impl<E> SavedErrand<E> {
+    /// Swaps the entity references, or returns `None` if one of them can't be mapped.
+    pub fn try_map<F>(self, mut map: impl FnMut(E) -> Option<F>) -> Option<SavedErrand<F>> {
+        Some(match self {
+            SavedErrand::MoveToPosition {
+                target,
+                search_radius,
+            } => SavedErrand::MoveToPosition {
+                target,
+                search_radius,
+            },
+            SavedErrand::Sleep { duration } => SavedErrand::Sleep { duration },
+            SavedErrand::MineWall { target } => SavedErrand::MineWall {
+                target: map(target)?,
+            },
+            SavedErrand::CarryToDepot { item } => SavedErrand::CarryToDepot { item: map(item)? },
+            SavedErrand::Build { site } => SavedErrand::Build { site: map(site)? },
+            SavedErrand::DemolishBuilding { target } => SavedErrand::DemolishBuilding {
+                target: map(target)?,
+            },
+        })
+    }
+}
+
+impl<E> SavedQueuedErrand<E> {
+    pub fn try_map<F>(self, mut map: impl FnMut(E) -> Option<F>) -> Option<SavedQueuedErrand<F>> {
+        let designation = match self.designation {
+            Some(designation) => Some(map(designation)?),
+            None => None,
+        };
+
+        Some(SavedQueuedErrand {
+            errand: self.errand.try_map(&mut map)?,
+            designation,
+            fail_if_missing: self
+                .fail_if_missing
+                .into_iter()
+                .map(&mut map)
+                .collect::<Option<_>>()?,
+        })
+    }
+}
+
+impl SavedErrand<Entity> {
+    pub fn designate(&self, entity: Entity) -> Designation {
+        match self.clone() {
+            SavedErrand::MoveToPosition {
+                target,
+                search_radius,
+            } => Designation::new(entity, MoveToPosition::new(target, search_radius)),
+            SavedErrand::Sleep { duration } => Designation::new(entity, SleepErrand { duration }),
+            SavedErrand::MineWall { target } => {
+                Designation::new(entity, MineWallErrand::new(target))
+            }
+            SavedErrand::CarryToDepot { item } => {
+                Designation::new(entity, CarryToDepotErrand::new(item))
+            }
+            SavedErrand::Build { site } => Designation::new(entity, BuildErrand::new(site)),
+            SavedErrand::DemolishBuilding { target } => {
+                Designation::new(entity, DemolishBuildingErrand::new(target))
+            }
+        }
+    }
+
+    pub fn append_to(&self, queue: &mut ErrandQueue, fail_if_missing: &[Entity]) {
+        match self.clone() {
+            SavedErrand::MoveToPosition {
+                target,
+                search_radius,
+            } => append(
+                queue,
+                MoveToPosition::new(target, search_radius),
+                fail_if_missing,
+            ),
+            SavedErrand::Sleep { duration } => {
+                append(queue, SleepErrand { duration }, fail_if_missing)
+            }
+            SavedErrand::MineWall { target } => {
+                append(queue, MineWallErrand::new(target), fail_if_missing)
+            }
+            SavedErrand::CarryToDepot { item } => {
+                append(queue, CarryToDepotErrand::new(item), fail_if_missing)
+            }
+            SavedErrand::Build { site } => append(queue, BuildErrand::new(site), fail_if_missing),
+            SavedErrand::DemolishBuilding { target } => append(
+                queue,
+                DemolishBuildingErrand::new(target),
+                fail_if_missing,
+            ),
+        }
+    }
+}
+
+fn append<T: Errand>(queue: &mut ErrandQueue, errand: T, fail_if_missing: &[Entity]) {
+    queue.append_errand(|id| {
+        let mut e = QueuedErrandImpl::new(id, errand);
+        for entity in fail_if_missing {
+            e.fail_if_entity_missing(*entity);
+        }
+
+        e
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mapping_fails_when_a_reference_is_missing() {
+        let saved = SavedQueuedErrand {
+            errand: SavedErrand::MineWall { target: 1 },
+            designation: Some(1),
+            fail_if_missing: vec![1, 2],
+        };
+
+        assert_eq!(
+            saved.clone().try_map(|e| Some(e * 10)),
+            Some(SavedQueuedErrand {
+                errand: SavedErrand::MineWall { target: 10 },
+                designation: Some(10),
+                fail_if_missing: vec![10, 20],
+            })
+        );
+        assert_eq!(saved.try_map(|e| (e == 1).then_some(e)), None);
+    }
+
+    #[test]
+    fn queued_errands_remember_their_designation() {
+        let target = Entity::from_raw(7);
+        let designation = SavedErrand::MineWall { target }.designate(target);
+
+        let mut queue = ErrandQueue::new();
+        assert!(designation.enqueue(Entity::from_raw(1), &mut queue, 0.0));
+
+        assert_eq!(
+            queue.save(),
+            vec![SavedQueuedErrand {
+                errand: SavedErrand::MineWall { target },
+                designation: Some(target),
+                fail_if_missing: vec![target],
+            }]
+        );
+        assert_eq!(designation.save(), SavedErrand::MineWall { target });
+    }
+}
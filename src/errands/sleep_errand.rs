@@ -1,4 +1,4 @@
-use crate::errands::WorkingOnErrand;
+use crate::errands::{ErrandTarget, SavedErrand, WorkingOnErrand};
 use crate::prelude::*;
 
 #[derive(Clone, Debug)]
@@ -12,6 +12,20 @@ impl Errand for SleepErrand {
     fn get_errand_type_order() -> i32 {
         1000
     }
+
+    fn name() -> &'static str {
+        "Sleep"
+    }
+
+    fn target(&self) -> Option<ErrandTarget<Entity>> {
+        None
+    }
+
+    fn save(&self) -> SavedErrand<Entity> {
+        SavedErrand::Sleep {
+            duration: self.duration,
+        }
+    }
 }
 
 pub fn execute_sleep_errand(
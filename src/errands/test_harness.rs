@@ -0,0 +1,556 @@
+//! A bare app with only the errand scheduling systems, for testing how queued errands are
+//! started, finished, overwritten and failed.
+
+use crate::errands::sleep_errand::{execute_sleep_errand, SleepErrand};
+use crate::errands::{
+    Designation, ErrandFailureReason, ErrandsV2AppExtensions, ErrandsV2Plugin, IsWorking,
+    QueuedErrandFailureBuilder, QueuedErrandImpl, SavedErrand, WorkerPriorities, WorkingOnErrand,
+};
+use crate::prelude::*;
+pub use crate::test_app::{TestAppExtensions, TEST_TIMESTEP};
+
+/// Sleep errands are registered by default. Their duration makes them easy to tell apart in
+/// assertions, and a long one keeps a worker busy until the test finishes it explicitly.
+pub fn errand_test_app() -> App {
+    let mut app = App::new();
+
+    app.add_plugins((MinimalPlugins, TransformPlugin))
+        .use_test_timestep()
+        .add_plugins(ErrandsV2Plugin)
+        .add_systems(Update, execute_sleep_errand)
+        .add_errand::<SleepErrand>();
+
+    app
+}
+
+pub fn sleep(duration: f32) -> SleepErrand {
+    SleepErrand { duration }
+}
+
+pub fn slept(duration: f32) -> SavedErrand<Entity> {
+    SavedErrand::Sleep { duration }
+}
+
+pub trait ErrandTestAppExtensions {
+    fn spawn_worker(&mut self, position: Vec3) -> Entity;
+    fn spawn_target(&mut self, position: Vec3) -> Entity;
+    fn queue(&mut self, worker: Entity) -> Mut<'_, ErrandQueue>;
+    /// Appends an errand that fails as soon as `target` is despawned.
+    fn append_with_target<E: Errand>(&mut self, worker: Entity, errand: E, target: Entity);
+    fn designate<E: Errand>(&mut self, target: Entity, errand: E);
+    fn finish_errand<E: Errand>(&mut self, worker: Entity);
+    fn fail_errand<E: Errand>(&mut self, worker: Entity, reason: ErrandFailureReason);
+
+    fn queued(&self, worker: Entity) -> Vec<SavedErrand<Entity>>;
+    fn working_on<E: Errand>(&self, worker: Entity) -> Option<&WorkingOnErrand<E>>;
+    fn is_working(&self, worker: Entity) -> bool;
+    /// Panics unless the worker is working on the errand at the front of its queue.
+    fn assert_working_on_front<E: Errand>(&self, worker: Entity);
+    /// Keeps every event of this type from now on, see [`Self::recorded`].
+    fn record_events<T: Event + Clone>(&mut self);
+    fn recorded<T: Event + Clone>(&self) -> Vec<T>;
+}
+
+#[derive(Resource)]
+struct Recorded<T>(Vec<T>);
+
+fn record<T: Event + Clone>(mut events: EventReader<T>, mut recorded: ResMut<Recorded<T>>) {
+    recorded.0.extend(events.iter().cloned());
+}
+
+impl ErrandTestAppExtensions for App {
+    fn spawn_worker(&mut self, position: Vec3) -> Entity {
+        self.world
+            .spawn((
+                ErrandQueue::new(),
+                WorkerPriorities::default(),
+                TransformBundle::from_transform(Transform::from_translation(position)),
+            ))
+            .id()
+    }
+
+    fn spawn_target(&mut self, position: Vec3) -> Entity {
+        self.world
+            .spawn(TransformBundle::from_transform(Transform::from_translation(
+                position,
+            )))
+            .id()
+    }
+
+    fn queue(&mut self, worker: Entity) -> Mut<'_, ErrandQueue> {
+        self.world
+            .get_mut::<ErrandQueue>(worker)
+            .expect("Worker should have an errand queue")
+    }
+
+    fn append_with_target<E: Errand>(&mut self, worker: Entity, errand: E, target: Entity) {
+        self.queue(worker).append_errand(|id| {
+            let mut e = QueuedErrandImpl::new(id, errand);
+            e.fail_if_entity_missing(target);
+
+            e
+        });
+    }
+
+    fn designate<E: Errand>(&mut self, target: Entity, errand: E) {
+        self.world
+            .entity_mut(target)
+            .insert(Designation::new(target, errand));
+    }
+
+    fn finish_errand<E: Errand>(&mut self, worker: Entity) {
+        self.world
+            .get_mut::<WorkingOnErrand<E>>(worker)
+            .expect("Worker should be working on the errand")
+            .done();
+    }
+
+    fn fail_errand<E: Errand>(&mut self, worker: Entity, reason: ErrandFailureReason) {
+        self.world
+            .get_mut::<WorkingOnErrand<E>>(worker)
+            .expect("Worker should be working on the errand")
+            .fail(reason);
+    }
+
+    fn queued(&self, worker: Entity) -> Vec<SavedErrand<Entity>> {
+        self.world
+            .get::<ErrandQueue>(worker)
+            .expect("Worker should have an errand queue")
+            .save()
+            .into_iter()
+            .map(|e| e.errand)
+            .collect()
+    }
+
+    fn working_on<E: Errand>(&self, worker: Entity) -> Option<&WorkingOnErrand<E>> {
+        self.world.get::<WorkingOnErrand<E>>(worker)
+    }
+
+    fn is_working(&self, worker: Entity) -> bool {
+        self.world.get::<IsWorking>(worker).is_some()
+    }
+
+    fn assert_working_on_front<E: Errand>(&self, worker: Entity) {
+        let working_on = self
+            .working_on::<E>(worker)
+            .expect("Worker should be working on an errand");
+        let front = self.world.get::<ErrandQueue>(worker).unwrap().front_id();
+
+        assert_eq!(Some(working_on.id()), front);
+        assert!(self.is_working(worker));
+    }
+
+    fn record_events<T: Event + Clone>(&mut self) {
+        self.insert_resource(Recorded::<T>(Vec::new()))
+            .add_systems(Last, record::<T>);
+    }
+
+    fn recorded<T: Event + Clone>(&self) -> Vec<T> {
+        self.world
+            .get_resource::<Recorded<T>>()
+            .expect("Events should be recorded")
+            .0
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errands::{
+        ErrandCompleted, ErrandEnqueued, ErrandFailed, ErrandPreempted, ErrandRetrying,
+        ErrandStarted,
+        ErrandTarget, MineWallErrand, MoveToPosition, RetryPolicy, FAILED_WORKER_COOLDOWN,
+    };
+    use std::any::TypeId;
+
+    #[test]
+    fn starts_the_first_queued_errand() {
+        let mut app = errand_test_app();
+        let worker = app.spawn_worker(Vec3::ZERO);
+        app.queue(worker).append_independent_errand(sleep(100.0));
+
+        app.step(2);
+
+        app.assert_working_on_front::<SleepErrand>(worker);
+    }
+
+    #[test]
+    fn finishing_an_errand_starts_the_next_one() {
+        let mut app = errand_test_app();
+        let worker = app.spawn_worker(Vec3::ZERO);
+        app.queue(worker).append_independent_errand(sleep(100.0));
+        app.queue(worker).append_independent_errand(sleep(50.0));
+        app.step(2);
+
+        app.finish_errand::<SleepErrand>(worker);
+        app.step(2);
+
+        assert_eq!(app.queued(worker), vec![slept(50.0)]);
+        app.assert_working_on_front::<SleepErrand>(worker);
+        assert!(app.working_on::<SleepErrand>(worker).unwrap().duration > 49.0);
+    }
+
+    #[test]
+    fn stops_working_when_the_queue_runs_out() {
+        let mut app = errand_test_app();
+        let worker = app.spawn_worker(Vec3::ZERO);
+        app.queue(worker).append_independent_errand(sleep(0.1));
+
+        app.step(20);
+
+        assert!(app.queued(worker).is_empty());
+        assert!(app.working_on::<SleepErrand>(worker).is_none());
+        assert!(!app.is_working(worker));
+    }
+
+    #[test]
+    fn prepended_errand_overwrites_the_current_one() {
+        let mut app = errand_test_app();
+        let worker = app.spawn_worker(Vec3::ZERO);
+        app.queue(worker).append_independent_errand(sleep(100.0));
+        app.step(2);
+
+        app.queue(worker)
+            .prepend_errand(|id| QueuedErrandImpl::new(id, sleep(50.0)));
+        app.step(2);
+
+        assert_eq!(app.queued(worker), vec![slept(50.0), slept(100.0)]);
+        app.assert_working_on_front::<SleepErrand>(worker);
+
+        app.finish_errand::<SleepErrand>(worker);
+        app.step(2);
+
+        assert_eq!(app.queued(worker), vec![slept(100.0)]);
+        app.assert_working_on_front::<SleepErrand>(worker);
+    }
+
+    #[test]
+    fn errand_fails_when_its_target_is_removed() {
+        let mut app = errand_test_app();
+        let worker = app.spawn_worker(Vec3::ZERO);
+        let target = app.spawn_target(Vec3::X);
+        app.append_with_target(worker, sleep(100.0), target);
+        app.queue(worker).append_independent_errand(sleep(50.0));
+        app.step(2);
+
+        app.world.despawn(target);
+        app.step(2);
+
+        assert_eq!(app.queued(worker), vec![slept(50.0)]);
+        app.assert_working_on_front::<SleepErrand>(worker);
+    }
+
+    #[test]
+    fn designation_is_only_taken_by_one_worker() {
+        let mut app = errand_test_app();
+        let near = app.spawn_worker(Vec3::ZERO);
+        let far = app.spawn_worker(Vec3::X * 50.0);
+        let target = app.spawn_target(Vec3::X);
+        app.designate(target, sleep(100.0));
+
+        app.step(3);
+
+        let taken = [near, far]
+            .into_iter()
+            .filter(|w| !app.queued(*w).is_empty())
+            .count();
+        assert_eq!(taken, 1);
+    }
+
+    #[test]
+    fn sends_lifecycle_events() {
+        let mut app = errand_test_app();
+        app.record_events::<ErrandEnqueued>();
+        app.record_events::<ErrandStarted>();
+        app.record_events::<ErrandCompleted>();
+        let worker = app.spawn_worker(Vec3::ZERO);
+        app.queue(worker).append_independent_errand(sleep(100.0));
+        app.step(2);
+
+        let errand_id = app.queue(worker).front_id().unwrap();
+        app.finish_errand::<SleepErrand>(worker);
+        app.step(2);
+
+        assert_eq!(
+            app.recorded::<ErrandEnqueued>(),
+            vec![ErrandEnqueued {
+                worker,
+                errand_id,
+                errand_type: "SleepErrand",
+            }]
+        );
+        assert_eq!(
+            app.recorded::<ErrandStarted>(),
+            vec![ErrandStarted {
+                worker,
+                errand_id,
+                errand_type: "SleepErrand",
+            }]
+        );
+        assert_eq!(
+            app.recorded::<ErrandCompleted>(),
+            vec![ErrandCompleted {
+                worker,
+                errand_id,
+                errand_type: "SleepErrand",
+            }]
+        );
+    }
+
+    #[test]
+    fn removed_target_fails_the_errand() {
+        let mut app = errand_test_app();
+        app.record_events::<ErrandFailed>();
+        app.record_events::<ErrandCompleted>();
+        let worker = app.spawn_worker(Vec3::ZERO);
+        let target = app.spawn_target(Vec3::X);
+        app.append_with_target(worker, sleep(100.0), target);
+        app.step(2);
+
+        let errand_id = app.queue(worker).front_id().unwrap();
+        app.world.despawn(target);
+        app.step(2);
+
+        assert_eq!(
+            app.recorded::<ErrandFailed>(),
+            vec![ErrandFailed {
+                worker,
+                errand_id,
+                errand_type: "SleepErrand",
+                reason: ErrandFailureReason::TargetRemoved,
+            }]
+        );
+        assert!(app.recorded::<ErrandCompleted>().is_empty());
+    }
+
+    #[test]
+    fn failing_the_current_errand_reports_the_reason() {
+        let mut app = errand_test_app();
+        app.record_events::<ErrandFailed>();
+        let worker = app.spawn_worker(Vec3::ZERO);
+        app.queue(worker).append_independent_errand(sleep(100.0));
+        app.queue(worker).append_independent_errand(sleep(50.0));
+        app.step(2);
+
+        let errand_id = app.queue(worker).front_id().unwrap();
+        app.fail_errand::<SleepErrand>(worker, ErrandFailureReason::PathNotFound);
+        app.step(2);
+
+        assert_eq!(
+            app.recorded::<ErrandFailed>(),
+            vec![ErrandFailed {
+                worker,
+                errand_id,
+                errand_type: "SleepErrand",
+                reason: ErrandFailureReason::PathNotFound,
+            }]
+        );
+        assert_eq!(app.queued(worker), vec![slept(50.0)]);
+        app.assert_working_on_front::<SleepErrand>(worker);
+    }
+
+    #[test]
+    fn prepending_preempts_the_current_errand() {
+        let mut app = errand_test_app();
+        app.record_events::<ErrandPreempted>();
+        app.record_events::<ErrandStarted>();
+        let worker = app.spawn_worker(Vec3::ZERO);
+        app.queue(worker).append_independent_errand(sleep(100.0));
+        app.step(2);
+
+        let preempted_id = app.queue(worker).front_id().unwrap();
+        app.queue(worker)
+            .prepend_errand(|id| QueuedErrandImpl::new(id, sleep(50.0)));
+        app.step(2);
+
+        assert_eq!(
+            app.recorded::<ErrandPreempted>(),
+            vec![ErrandPreempted {
+                worker,
+                errand_id: preempted_id,
+                errand_type: "SleepErrand",
+            }]
+        );
+        assert_eq!(app.recorded::<ErrandStarted>().len(), 2);
+    }
+
+    #[test]
+    fn cleared_errands_are_cancelled() {
+        let mut app = errand_test_app();
+        app.record_events::<ErrandEnqueued>();
+        app.record_events::<ErrandFailed>();
+        let worker = app.spawn_worker(Vec3::ZERO);
+        app.queue(worker).append_independent_errand(sleep(100.0));
+        app.queue(worker).append_independent_errand(sleep(50.0));
+        app.step(2);
+        app.assert_working_on_front::<SleepErrand>(worker);
+
+        let mut queue = app.queue(worker);
+        queue.append_independent_errand(sleep(25.0));
+        queue.clear();
+        app.step(2);
+
+        assert_eq!(app.recorded::<ErrandEnqueued>().len(), 2);
+        let failed = app.recorded::<ErrandFailed>();
+        assert_eq!(failed.len(), 3);
+        assert!(failed
+            .iter()
+            .all(|e| e.reason == ErrandFailureReason::Cancelled));
+        assert!(!app.is_working(worker));
+    }
+
+    #[test]
+    fn failed_errand_is_retried_after_a_backoff() {
+        let mut app = errand_test_app();
+        app.record_events::<ErrandFailed>();
+        app.record_events::<ErrandRetrying>();
+        let worker = app.spawn_worker(Vec3::ZERO);
+        app.queue(worker).append_errand(|id| {
+            QueuedErrandImpl::new(id, sleep(100.0)).with_retry_policy(RetryPolicy::Retry {
+                attempts: 1,
+                backoff: 0.5,
+            })
+        });
+        app.step(2);
+
+        app.fail_errand::<SleepErrand>(worker, ErrandFailureReason::PathNotFound);
+        app.step(2);
+
+        assert_eq!(app.queued(worker), vec![slept(100.0)]);
+        assert!(!app.is_working(worker));
+        assert!(app.recorded::<ErrandFailed>().is_empty());
+        let retrying = app.recorded::<ErrandRetrying>();
+        assert_eq!(retrying.len(), 1);
+        assert_eq!(retrying[0].worker, worker);
+        assert_eq!(retrying[0].reason, ErrandFailureReason::PathNotFound);
+        assert_eq!(retrying[0].attempt, 1);
+
+        app.step((0.5 / TEST_TIMESTEP) as usize + 1);
+        app.assert_working_on_front::<SleepErrand>(worker);
+
+        app.fail_errand::<SleepErrand>(worker, ErrandFailureReason::PathNotFound);
+        app.step(2);
+
+        assert!(app.queued(worker).is_empty());
+        assert_eq!(app.recorded::<ErrandFailed>().len(), 1);
+        assert_eq!(app.recorded::<ErrandRetrying>().len(), 1);
+    }
+
+    #[test]
+    fn failed_designation_goes_to_another_worker() {
+        let mut app = errand_test_app();
+        let near = app.spawn_worker(Vec3::ZERO);
+        let far = app.spawn_worker(Vec3::X * 50.0);
+        let target = app.spawn_target(Vec3::X);
+        app.designate(target, sleep(100.0));
+        app.step(3);
+
+        let (failing, other) = if app.queued(near).is_empty() {
+            (far, near)
+        } else {
+            (near, far)
+        };
+        app.fail_errand::<SleepErrand>(failing, ErrandFailureReason::PathNotFound);
+        app.step(5);
+
+        assert!(app.queued(failing).is_empty());
+        assert_eq!(app.queued(other), vec![slept(100.0)]);
+        assert!(app.world.get::<Designation>(target).is_some());
+
+        app.fail_errand::<SleepErrand>(other, ErrandFailureReason::PathNotFound);
+        app.step(5);
+
+        assert!(app.queued(failing).is_empty(), "failed workers shouldn't take it right away");
+        assert!(app.queued(other).is_empty());
+    }
+
+    #[test]
+    fn failed_designation_is_taken_again_after_the_cooldown() {
+        let mut app = errand_test_app();
+        let worker = app.spawn_worker(Vec3::ZERO);
+        let target = app.spawn_target(Vec3::X);
+        app.designate(target, sleep(100.0));
+        app.step(3);
+
+        app.fail_errand::<SleepErrand>(worker, ErrandFailureReason::PathNotFound);
+        app.step(5);
+        assert!(app.queued(worker).is_empty());
+
+        app.step((FAILED_WORKER_COOLDOWN / TEST_TIMESTEP) as usize);
+
+        assert_eq!(app.queued(worker), vec![slept(100.0)]);
+    }
+
+    #[test]
+    fn failed_designation_is_taken_again_once_someone_else_succeeds() {
+        let mut app = errand_test_app();
+        let near = app.spawn_worker(Vec3::ZERO);
+        let far = app.spawn_worker(Vec3::X * 50.0);
+        let target = app.spawn_target(Vec3::X);
+        app.designate(target, sleep(100.0));
+        app.step(3);
+
+        let (failing, other) = if app.queued(near).is_empty() {
+            (far, near)
+        } else {
+            (near, far)
+        };
+        app.fail_errand::<SleepErrand>(failing, ErrandFailureReason::PathNotFound);
+        app.step(5);
+        assert_eq!(app.queued(other), vec![slept(100.0)]);
+
+        app.world
+            .get_mut::<WorkerPriorities>(other)
+            .unwrap()
+            .set_priority(TypeId::of::<SleepErrand>(), None);
+        app.finish_errand::<SleepErrand>(other);
+        app.step(5);
+
+        assert_eq!(app.queued(failing), vec![slept(100.0)]);
+    }
+
+    #[test]
+    fn disabled_errand_types_are_not_taken() {
+        let mut app = errand_test_app();
+        let worker = app.spawn_worker(Vec3::ZERO);
+        app.step(1);
+
+        let sleep_type = TypeId::of::<SleepErrand>();
+        app.world
+            .get_mut::<WorkerPriorities>(worker)
+            .unwrap()
+            .set_priority(sleep_type, None);
+        let target = app.spawn_target(Vec3::X);
+        app.designate(target, sleep(100.0));
+        app.step(3);
+
+        assert!(app.queued(worker).is_empty());
+
+        app.world
+            .get_mut::<WorkerPriorities>(worker)
+            .unwrap()
+            .set_priority(sleep_type, Some(1));
+        app.step(3);
+
+        assert_eq!(app.queued(worker), vec![slept(100.0)]);
+    }
+
+    #[test]
+    fn sleeping_has_no_target() {
+        let target = Entity::from_raw(3);
+        let mut queue = ErrandQueue::new();
+        queue.append_independent_errand(MineWallErrand::new(target));
+        queue.append_independent_errand(MoveToPosition::new(Vec3::X, None));
+        queue.append_independent_errand(sleep(1.0));
+
+        assert_eq!(
+            queue.iter().map(|e| e.target()).collect_vec(),
+            vec![
+                Some(ErrandTarget::Entity(target)),
+                Some(ErrandTarget::Position(Vec3::X)),
+                None
+            ]
+        );
+    }
+}
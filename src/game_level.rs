@@ -1,29 +1,149 @@
 use crate::grid::{flood_fill_grid, Grid, GridPosition};
 use crate::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
-#[derive(Resource, Eq, PartialEq, Debug)]
+#[derive(Resource, Serialize, Deserialize, Eq, PartialEq, Debug, Clone)]
 pub struct GameLevel {
     open_tiles: Grid<bool>,
     walled_tiles: Grid<bool>,
+    wall_types: Grid<WallType>,
+    seams: Grid<Option<Seam>>,
+    discovered_tiles: Grid<bool>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Eq, PartialEq, Hash, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceType {
+    Ore,
+    Crystal,
+}
+
+impl ResourceType {
+    pub const ALL: [ResourceType; 2] = [ResourceType::Ore, ResourceType::Crystal];
+
+    pub fn color(&self) -> Color {
+        match self {
+            ResourceType::Ore => Color::rgb(0.8, 0.45, 0.15),
+            ResourceType::Crystal => Color::rgb(0.2, 0.9, 0.4),
+        }
+    }
+}
+
+/// Resources embedded in a wall, dropped when the wall is mined.
+#[derive(Serialize, Deserialize, Eq, PartialEq, Debug, Clone, Copy)]
+pub struct Seam {
+    pub resource_type: ResourceType,
+    pub yield_count: u32,
+}
+
+/// Sent when mining opens up a path to tiles the player has not seen before.
+#[derive(Event, Debug)]
+pub struct TilesDiscovered {
+    pub positions: Vec<GridPosition>,
+}
+
+/// The material a wall is made of.
+#[derive(Serialize, Deserialize, JsonSchema, Eq, PartialEq, Hash, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WallType {
+    #[default]
+    Dirt,
+    LooseRock,
+    HardRock,
+    SolidRock,
+}
+
+impl WallType {
+    pub const ALL: [WallType; 4] = [
+        WallType::Dirt,
+        WallType::LooseRock,
+        WallType::HardRock,
+        WallType::SolidRock,
+    ];
+
+    /// Seconds of work a single miner needs to break through the wall.
+    pub fn mining_time(&self) -> Option<f32> {
+        match self {
+            WallType::Dirt => Some(3.),
+            WallType::LooseRock => Some(5.),
+            WallType::HardRock => Some(12.),
+            WallType::SolidRock => None,
+        }
+    }
+
+    pub fn ore_drops(&self) -> u32 {
+        match self {
+            WallType::Dirt => 0,
+            WallType::LooseRock => 1,
+            WallType::HardRock => 2,
+            WallType::SolidRock => 0,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            WallType::Dirt => Color::rgb(0.75, 0.6, 0.45),
+            WallType::LooseRock => Color::rgb(0.8, 0.8, 0.75),
+            WallType::HardRock => Color::rgb(0.5, 0.5, 0.55),
+            WallType::SolidRock => Color::rgb(0.25, 0.25, 0.3),
+        }
+    }
 }
 
 pub const TILE_SIZE: f32 = 10.0;
 pub const HALF_TILE_SIZE: f32 = TILE_SIZE / 2.0;
 
 impl GameLevel {
+    #[cfg(test)]
     pub fn new(height: i32, width: i32) -> Self {
         Self {
             open_tiles: Grid::new(width, height, false),
             walled_tiles: Grid::new(width, height, true),
+            wall_types: Grid::new(width, height, WallType::default()),
+            seams: Grid::new(width, height, None),
+            discovered_tiles: Grid::new(width, height, false),
         }
     }
 
     pub fn new_from_open_tiles(grid: Grid<bool>) -> Self {
-        Self {
+        let mut level = Self {
             open_tiles: grid.clone(),
             walled_tiles: grid.map(|b| !*b),
+            wall_types: grid.map(|_| WallType::default()),
+            seams: grid.map(|_| None),
+            discovered_tiles: grid.map(|_| false),
+        };
+
+        let open = level.iter_open_tiles().collect_vec();
+        level.discover_around(open);
+
+        level
+    }
+
+    /// Creates a level where only the carved out tiles connected to `discovered_from` are open.
+    /// Every other carved out tile is part of a hidden cavern until a wall leading to it is mined.
+    pub fn new_from_carved_tiles(carved: Grid<bool>, discovered_from: &[GridPosition]) -> Self {
+        let mut level = Self {
+            open_tiles: carved.map(|_| false),
+            walled_tiles: carved.map(|b| !*b),
+            wall_types: carved.map(|_| WallType::default()),
+            seams: carved.map(|_| None),
+            discovered_tiles: carved.map(|_| false),
+        };
+
+        for start in discovered_from {
+            if level.walled_tiles.get(start.x, start.z) == Some(&false) {
+                level.open_tiles.set(start.x, start.z, true);
+                level.expand_open_tiles(start.x, start.z);
+            }
         }
+
+        let open = level.iter_open_tiles().collect_vec();
+        level.discover_around(open);
+
+        level
     }
 
     pub fn width(&self) -> i32 {
@@ -34,7 +154,9 @@ impl GameLevel {
         self.open_tiles.height()
     }
 
-    pub fn remove_wall(&mut self, x: i32, z: i32) {
+    /// Removes the wall, along with any walls left too thin to stand on their own, and
+    /// returns the tiles that became discovered because of it.
+    pub fn remove_wall(&mut self, x: i32, z: i32) -> Vec<GridPosition> {
         self.walled_tiles.set(x, z, false);
         self.open_tiles.set(x, z, true);
 
@@ -87,12 +209,14 @@ impl GameLevel {
             }
         }
 
-        self.expand_open_tiles(x, z);
+        let opened = self.expand_open_tiles(x, z);
+
+        self.discover_around(opened)
     }
 
-    fn expand_open_tiles(&mut self, x: i32, z: i32) {
+    fn expand_open_tiles(&mut self, x: i32, z: i32) -> Vec<GridPosition> {
         if !self.is_open(x, z) {
-            return;
+            return Vec::new();
         }
 
         let walled_tiles = &self.walled_tiles;
@@ -100,9 +224,56 @@ impl GameLevel {
             !*walled_tiles.get(x, y).unwrap_or(&true)
         });
 
-        for pos in matched {
+        for pos in matched.iter() {
             self.open_tiles.set(pos.x, pos.z, true);
         }
+
+        matched
+    }
+
+    /// Marks the given open tiles and everything around them as discovered, returning the
+    /// tiles that were not already discovered.
+    fn discover_around(&mut self, open: Vec<GridPosition>) -> Vec<GridPosition> {
+        let mut discovered = Vec::new();
+
+        for pos in open {
+            for x in pos.x - 1..=pos.x + 1 {
+                for z in pos.z - 1..=pos.z + 1 {
+                    if let Some(tile) = self.discovered_tiles.get_mut(x, z) {
+                        if !*tile {
+                            *tile = true;
+                            discovered.push(GridPosition::new(x, z));
+                        }
+                    }
+                }
+            }
+        }
+
+        discovered
+    }
+
+    pub fn is_discovered(&self, x: i32, z: i32) -> bool {
+        *self.discovered_tiles.get(x, z).unwrap_or(&false)
+    }
+
+    fn iter_open_tiles(&self) -> impl Iterator<Item = GridPosition> + '_ {
+        self.iter_tiles().filter(|p| self.is_open(p.x, p.z))
+    }
+
+    pub fn wall_type(&self, x: i32, z: i32) -> WallType {
+        self.wall_types.get(x, z).copied().unwrap_or_default()
+    }
+
+    pub fn set_wall_type(&mut self, x: i32, z: i32, wall_type: WallType) {
+        self.wall_types.set(x, z, wall_type);
+    }
+
+    pub fn seam(&self, x: i32, z: i32) -> Option<Seam> {
+        self.seams.get(x, z).copied().flatten()
+    }
+
+    pub fn set_seam(&mut self, x: i32, z: i32, seam: Option<Seam>) {
+        self.seams.set(x, z, seam);
     }
 
     pub fn is_open(&self, x: i32, z: i32) -> bool {
@@ -126,6 +297,12 @@ impl GameLevel {
         Vec3::new(x, 0.0, z)
     }
 
+    /// The open tiles that can be walked to from `from` without squeezing diagonally between
+    /// two walls.
+    pub fn reachable_from(&self, from: GridPosition) -> Vec<GridPosition> {
+        flood_fill_grid(&self.open_tiles, from.x, from.z, |x, z| self.is_open(x, z))
+    }
+
     pub fn within(&self, x: i32, z: i32) -> bool {
         x >= 0 && x < self.width() && z >= 0 && z < self.height()
     }
@@ -219,6 +396,13 @@ mod tests {
                 3,
                 vec![true, true, false, false, true, false, true, true, false],
             ),
+            wall_types: Grid::new(3, 3, WallType::default()),
+            seams: Grid::new(3, 3, None),
+            discovered_tiles: Grid::new_from_list(
+                3,
+                3,
+                vec![true, true, false, true, true, false, true, true, false],
+            ),
         };
         level.remove_wall(1, 1);
 
@@ -233,6 +417,9 @@ mod tests {
                 3,
                 vec![true, true, false, false, false, false, true, true, false],
             ),
+            wall_types: Grid::new(3, 3, WallType::default()),
+            seams: Grid::new(3, 3, None),
+            discovered_tiles: Grid::new(3, 3, true),
         };
 
         assert_eq!(level, expected);
@@ -407,6 +594,39 @@ mod tests {
     }
 
 
+    fn hidden_cavern_level() -> GameLevel {
+        let mut carved = Grid::new(8, 3, false);
+        for x in [1, 4, 5, 6] {
+            carved.set(x, 1, true);
+        }
+
+        GameLevel::new_from_carved_tiles(carved, &[GridPosition::new(1, 1)])
+    }
+
+    #[test]
+    fn carved_tiles_away_from_start_are_hidden() {
+        let level = hidden_cavern_level();
+
+        assert!(level.is_open(1, 1));
+        assert!(level.is_discovered(2, 2));
+        assert!(!level.is_open(5, 1));
+        assert!(!level.is_discovered(5, 1));
+        assert!(!level.is_discovered(3, 1));
+    }
+
+    #[test]
+    fn mining_into_hidden_cavern_reveals_it() {
+        let mut level = hidden_cavern_level();
+
+        let discovered = level.remove_wall(2, 1);
+
+        assert!(level.is_open(6, 1));
+        assert!(level.is_discovered(7, 2));
+        assert!(discovered.contains(&GridPosition::new(5, 1)));
+        assert!(discovered.contains(&GridPosition::new(3, 0)));
+        assert!(!discovered.contains(&GridPosition::new(1, 1)));
+    }
+
     #[test]
     fn test_tile_positions() {
         let level = GameLevel::new(10, 10);
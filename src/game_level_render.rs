@@ -1,21 +1,29 @@
 use crate::errands::{Minable, Standable};
-use crate::game_level::{GameLevel, HALF_TILE_SIZE, TILE_SIZE};
+use crate::game_level::{
+    GameLevel, ResourceType, TilesDiscovered, WallType, HALF_TILE_SIZE, TILE_SIZE,
+};
 use crate::grid::GridPosition;
 use crate::prelude::*;
 use crate::{GameState, MyAssets};
 use oxidized_navigation::NavMeshAffector;
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use crate::buildings::OpenForBuilding;
 use crate::health::{DeathAction, Health, OnDeathAction};
+use crate::resource_items::ResourceModels;
+use bevy::hierarchy::despawn_with_children_recursive;
 
 pub struct GameLevelRenderPlugin;
 
 impl Plugin for GameLevelRenderPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update,
+        app.add_systems(OnEnter(GameState::Playing), create_wall_materials)
+        .add_systems(Update,
             spawn_map_content
                 .run_if(resource_exists::<MyAssets>())
                 .run_if(resource_exists::<GameLevel>())
+                .run_if(resource_exists::<WallMaterials>())
+                .run_if(resource_exists::<ResourceModels>())
                 .run_if(in_state(GameState::Playing)),
         )
         .add_systems(Update,
@@ -23,7 +31,8 @@ impl Plugin for GameLevelRenderPlugin {
                 .run_if(resource_exists::<GameLevel>())
                 .run_if(in_state(GameState::Playing)),
         )
-        .insert_resource(WorldTileTracker::default());
+        .insert_resource(WorldTileTracker::default())
+        .add_event::<TilesDiscovered>();
     }
 }
 
@@ -80,9 +89,49 @@ impl WorldTilePosition {
 }
 
 #[derive(Resource, Default)]
-struct WorldTileTracker {
+pub struct WorldTileTracker {
     tiles: HashMap<WorldTilePosition, WorldTile>,
     wall_entities: HashMap<Entity, WorldTilePosition>,
+    fog_entities: HashMap<WorldTilePosition, Entity>,
+}
+
+impl WorldTileTracker {
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    pub fn wall_at(&self, position: GridPosition) -> Option<Entity> {
+        self.tiles
+            .get(&WorldTilePosition {
+                x: position.x,
+                z: position.z,
+            })
+            .and_then(|tile| tile.wall_entity)
+    }
+
+    pub fn wall_position(&self, wall: Entity) -> Option<GridPosition> {
+        self.wall_entities.get(&wall).map(|p| p.as_grid_position())
+    }
+
+    fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.tiles
+            .values()
+            .flat_map(|tile| [Some(tile.floor_entity), tile.wall_entity])
+            .flatten()
+            .chain(self.fog_entities.values().copied())
+    }
+}
+
+/// Despawns every tile of the current map and forgets about them, so the next `GameLevel`
+/// is rendered from scratch instead of being diffed against the old one.
+pub fn despawn_map_content(commands: &mut Commands) {
+    commands.add(|world: &mut World| {
+        let tracker = std::mem::take(&mut *world.resource_mut::<WorldTileTracker>());
+
+        for entity in tracker.entities() {
+            despawn_with_children_recursive(world, entity);
+        }
+    });
 }
 
 struct WorldTile {
@@ -93,15 +142,99 @@ struct WorldTile {
 #[derive(Component)]
 struct Wall;
 
+#[derive(Component)]
+struct Fog;
+
+/// Wall types only differ in their material. The mesh is picked by [`get_wall_mesh`] from the
+/// neighbouring walls alone, so walls of different types still join up seamlessly and collide
+/// the same way.
+#[derive(Resource)]
+struct WallMaterials {
+    materials: HashMap<WallType, Handle<StandardMaterial>>,
+    seams: HashMap<ResourceType, Handle<StandardMaterial>>,
+    fog: Handle<StandardMaterial>,
+}
+
+impl WallMaterials {
+    fn get(&self, level: &GameLevel, position: &GridPosition) -> Handle<StandardMaterial> {
+        if let Some(seam) = level.seam(position.x, position.z) {
+            self.seams[&seam.resource_type].clone()
+        } else {
+            self.materials[&level.wall_type(position.x, position.z)].clone()
+        }
+    }
+}
+
+fn create_wall_materials(
+    mut commands: Commands,
+    my_assets: Res<MyAssets>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let base = materials
+        .get(&my_assets.wall_material)
+        .cloned()
+        .unwrap_or_default();
+
+    let wall_materials = WallType::ALL
+        .into_iter()
+        .map(|wall_type| {
+            let material = materials.add(StandardMaterial {
+                base_color: wall_type.color(),
+                ..base.clone()
+            });
+            (wall_type, material)
+        })
+        .collect();
+
+    let seams = [ResourceType::Ore, ResourceType::Crystal]
+        .into_iter()
+        .map(|resource_type| {
+            let material = materials.add(StandardMaterial {
+                base_color: resource_type.color(),
+                emissive: resource_type.color() * 0.2,
+                ..base.clone()
+            });
+            (resource_type, material)
+        })
+        .collect();
+
+    let fog = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.05, 0.05, 0.07),
+        perceptual_roughness: 1.0,
+        ..default()
+    });
+
+    commands.insert_resource(WallMaterials {
+        materials: wall_materials,
+        seams,
+        fog,
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
 fn spawn_map_content(
     level: Res<GameLevel>,
     mut commands: Commands,
     my_assets: Res<MyAssets>,
     mesh_assets: Res<Assets<Mesh>>,
     mut world_tile_tracker: ResMut<WorldTileTracker>,
+    wall_materials: Res<WallMaterials>,
+    resource_models: Res<ResourceModels>,
+    mut discovered_events: EventReader<TilesDiscovered>,
 ) {
     let mut updated_positions = Vec::new();
 
+    for discovered in discovered_events.iter().flat_map(|e| e.positions.iter()) {
+        let world_tile_position = WorldTilePosition {
+            x: discovered.x,
+            z: discovered.z,
+        };
+        if let Some(fog) = world_tile_tracker.fog_entities.remove(&world_tile_position) {
+            commands.entity(fog).despawn_recursive();
+            updated_positions.push(world_tile_position);
+        }
+    }
+
     for x in -1..=level.width() + 1 {
         for z in -1..=level.height() + 1 {
             let world_tile_position = WorldTilePosition { x, z };
@@ -121,10 +254,28 @@ fn spawn_map_content(
                         updated_positions.push(world_tile_position);
                     }
                 }
+            } else if level.within(x, z) && !level.is_discovered(x, z) {
+                if let Entry::Vacant(entry) =
+                    world_tile_tracker.fog_entities.entry(world_tile_position)
+                {
+                    let pos = level.get_position_at(GridPosition::new(x, z));
+                    let fog = commands.spawn((
+                        PbrBundle {
+                            transform: Transform::from_xyz(pos.x, HALF_TILE_SIZE, pos.z),
+                            material: wall_materials.fog.clone(),
+                            mesh: my_assets.full_wall_mesh.clone(),
+                            ..default()
+                        },
+                        Name::new(format!("Fog {} {}", pos.x, pos.z)),
+                        Fog,
+                    ));
+                    entry.insert(fog.id());
+                }
             } else {
                 let grid_position = GridPosition::new(x, z);
                 let pos = level.get_position_at(grid_position);
                 let mut wall_entity = None;
+                let wall_type = level.wall_type(x, z);
                 if let Some((wall_mesh, rotation)) =
                     get_wall_mesh(&level, &grid_position, &my_assets)
                 {
@@ -140,7 +291,7 @@ fn spawn_map_content(
                         PbrBundle {
                             transform: Transform::from_xyz(pos.x, HALF_TILE_SIZE, pos.z)
                                 .with_rotation(rotation),
-                            material: my_assets.wall_material.clone(),
+                            material: wall_materials.get(&level, &grid_position),
                             mesh: wall_mesh,
                             ..default()
                         },
@@ -152,15 +303,18 @@ fn spawn_map_content(
                     ));
 
                     if level.within(x, z) {
-                        wall_builder.insert((
-                            PlayerInteractable,
-                            Selectable::default(),
-                            Minable,
-                            Health::new(5.),
-                            OnDeathAction::new(SpawnOre {
-                                model: my_assets.ore_model.clone(),
-                            }),
-                        ));
+                        wall_builder.insert((PlayerInteractable, Selectable::default()));
+
+                        if let Some(mining_time) = wall_type.mining_time() {
+                            wall_builder.insert((
+                                Minable,
+                                Health::new(mining_time),
+                                OnDeathAction::new(SpawnResources::new(
+                                    resource_models.clone(),
+                                    get_resource_drops(&level, &grid_position),
+                                )),
+                            ));
+                        }
                     }
 
                     world_tile_tracker
@@ -234,7 +388,7 @@ fn spawn_map_content(
                             PbrBundle {
                                 transform: Transform::from_xyz(pos.x, HALF_TILE_SIZE, pos.z)
                                     .with_rotation(rotation),
-                                material: my_assets.wall_material.clone(),
+                                material: wall_materials.get(&level, &grid_position),
                                 mesh: wall_mesh,
                                 ..default()
                             },
@@ -252,10 +406,17 @@ fn update_game_level_when_wall_is_removed(
     mut tracker: ResMut<WorldTileTracker>,
     mut removed_walls: RemovedComponents<Wall>,
     mut commands: Commands,
+    mut discovered_events: EventWriter<TilesDiscovered>,
 ) {
     for entity in removed_walls.iter() {
         if let Some(pos) = tracker.wall_entities.remove(&entity) {
-            level.remove_wall(pos.x, pos.z);
+            let discovered = level.remove_wall(pos.x, pos.z);
+            if !discovered.is_empty() {
+                info!("Discovered {} new tiles", discovered.len());
+                discovered_events.send(TilesDiscovered {
+                    positions: discovered,
+                });
+            }
             if let Some(tile) = tracker.tiles.get(&pos) {
                 if let Some(mut commands) = commands.get_entity(tile.floor_entity) {
                     commands.insert(OpenForBuilding);
@@ -344,7 +505,7 @@ fn get_wall_mesh(
 
                     let mesh = my_assets.inner_diagonal_wall_mesh.clone();
 
-                    return Some((mesh, Quat::from_rotation_y(90.0f32.to_radians())));
+                    Some((mesh, Quat::from_rotation_y(90.0f32.to_radians())))
 
                 } else {
                     let mesh = my_assets.inner_corner_wall_mesh.clone();
@@ -373,26 +534,45 @@ fn get_wall_mesh(
     }
 }
 
-pub struct SpawnOre {
-    model: Handle<Scene>,
-}
+fn get_resource_drops(level: &GameLevel, position: &GridPosition) -> Vec<(ResourceType, u32)> {
+    let mut drops = vec![(
+        ResourceType::Ore,
+        level.wall_type(position.x, position.z).ore_drops(),
+    )];
 
-impl DeathAction for SpawnOre {
-    fn on_death(&self, _entity: Entity, commands: &mut Commands, transform: &GlobalTransform) {
-        info!("Spawning ore on wall death");
+    if let Some(seam) = level.seam(position.x, position.z) {
+        drops.push((seam.resource_type, seam.yield_count));
+    }
 
-        commands.spawn((
-            SceneBundle {
-                transform: Transform::from_translation(transform.translation()),
-                scene: self.model.clone(),
-                ..default()
-            },
-            RigidBody::Dynamic,
-            Collider::ball(0.5),
-        ));
+    drops
+}
 
+pub struct SpawnResources {
+    models: ResourceModels,
+    drops: Vec<(ResourceType, u32)>,
+}
+
+impl SpawnResources {
+    pub fn new(models: ResourceModels, drops: Vec<(ResourceType, u32)>) -> Self {
+        Self { models, drops }
     }
 }
 
+impl DeathAction for SpawnResources {
+    fn on_death(&self, _entity: Entity, commands: &mut Commands, transform: &GlobalTransform) {
+        let items = self
+            .drops
+            .iter()
+            .flat_map(|(resource_type, count)| std::iter::repeat_n(*resource_type, *count as usize));
+
+        for (i, resource_type) in items.enumerate() {
+            info!("Spawning {:?} on wall death", resource_type);
 
+            let angle = i as f32 * 2.4;
+            let offset = Vec3::new(angle.cos(), 0., angle.sin()) * (1. + i as f32 * 0.3);
 
+            self.models
+                .spawn(commands, resource_type, transform.translation() + offset);
+        }
+    }
+}
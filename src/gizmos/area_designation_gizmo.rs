@@ -0,0 +1,222 @@
+use crate::camera_control::Selector;
+use crate::errands::Designation;
+use crate::game_level::{GameLevel, TILE_SIZE};
+use crate::gizmos::{add_base_gizmo_systems, DesignationGizmo, GizmoTag, GizmoVisibility};
+use crate::grid::GridPosition;
+use crate::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_ecs::query::ReadOnlyWorldQuery;
+use bevy_prototype_debug_lines::DebugLines;
+use std::any::TypeId;
+use std::marker::PhantomData;
+
+/// A designation gizmo that can also be painted over an area of the map, instead of being
+/// applied to the selection.
+pub trait AreaDesignationGizmo: DesignationGizmo {
+    /// The entities that are designated when they are inside the painted area.
+    type Target: ReadOnlyWorldQuery + 'static;
+}
+
+/// Shown while nothing is selected, starts painting the designation of `G`.
+#[derive(Resource)]
+pub struct AreaGizmo<G> {
+    base: ButtonGizmo,
+    _gizmo: PhantomData<fn() -> G>,
+}
+
+impl<G> HasBaseGizmo for AreaGizmo<G> {
+    fn get_base_gizmo(&self) -> &ButtonGizmo {
+        &self.base
+    }
+}
+
+impl<G: AreaDesignationGizmo> GizmoVisibility for AreaGizmo<G> {
+    type WorldQuery = ();
+    type ReadOnlyWorldQuery = With<Selected>;
+
+    fn is_visible(query: &Query<Self::WorldQuery, Self::ReadOnlyWorldQuery>) -> bool {
+        query.is_empty()
+    }
+}
+
+impl<G: AreaDesignationGizmo> Gizmo for AreaGizmo<G> {
+    type Assets = G::Assets;
+
+    fn initialize(assets: &Self::Assets) -> Self {
+        let gizmo = G::initialize(assets);
+
+        Self {
+            base: ButtonGizmo::new(
+                gizmo.get_icon(),
+                &format!("{} area", gizmo.get_name()),
+                gizmo.get_order() + 1,
+            ),
+            _gizmo: PhantomData,
+        }
+    }
+}
+
+/// Left dragging paints the designation over the map until cancelled.
+#[derive(Resource)]
+pub struct PaintingDesignation {
+    gizmo: TypeId,
+    start: Option<GridPosition>,
+}
+
+pub fn is_painting_designation(painting: Option<Res<PaintingDesignation>>) -> bool {
+    painting.is_some()
+}
+
+fn is_painting<G: 'static>(painting: Option<Res<PaintingDesignation>>) -> bool {
+    painting.is_some_and(|p| p.gizmo == TypeId::of::<G>())
+}
+
+fn start_painting_when_gizmo_clicked<G: AreaDesignationGizmo>(
+    q: Query<&Interaction, (Changed<Interaction>, With<GizmoTag<AreaGizmo<G>>>)>,
+    mut commands: Commands,
+) {
+    if q.iter().any(|i| *i == Interaction::Pressed) {
+        commands.insert_resource(PaintingDesignation {
+            gizmo: TypeId::of::<G>(),
+            start: None,
+        });
+    }
+}
+
+/// The tiles between two corners, both included.
+fn area(a: GridPosition, b: GridPosition) -> (GridPosition, GridPosition) {
+    (
+        GridPosition::new(a.x.min(b.x), a.z.min(b.z)),
+        GridPosition::new(a.x.max(b.x), a.z.max(b.z)),
+    )
+}
+
+fn is_in_area((min, max): (GridPosition, GridPosition), tile: GridPosition) -> bool {
+    (min.x..=max.x).contains(&tile.x) && (min.z..=max.z).contains(&tile.z)
+}
+
+/// Draws the outline of every tile in the area, just above the walls.
+fn draw_area_grid(lines: &mut DebugLines, (min, max): (GridPosition, GridPosition)) {
+    const HEIGHT: f32 = TILE_SIZE + 0.1;
+    let color = Color::rgb(1.0, 0.8, 0.2);
+
+    let (x_start, x_end) = (min.x as f32 * TILE_SIZE, (max.x + 1) as f32 * TILE_SIZE);
+    let (z_start, z_end) = (min.z as f32 * TILE_SIZE, (max.z + 1) as f32 * TILE_SIZE);
+
+    for x in min.x..=max.x + 1 {
+        let x = x as f32 * TILE_SIZE;
+        lines.line_colored(
+            Vec3::new(x, HEIGHT, z_start),
+            Vec3::new(x, HEIGHT, z_end),
+            0.,
+            color,
+        );
+    }
+
+    for z in min.z..=max.z + 1 {
+        let z = z as f32 * TILE_SIZE;
+        lines.line_colored(
+            Vec3::new(x_start, HEIGHT, z),
+            Vec3::new(x_end, HEIGHT, z),
+            0.,
+            color,
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn paint_designation<G: AreaDesignationGizmo>(
+    mut painting: ResMut<PaintingDesignation>,
+    control: Query<(&ActionState<ControlAction>, &Camera, &GlobalTransform), With<Selector>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    ui_buttons: Query<&Interaction>,
+    targets: Query<(Entity, &GlobalTransform, Option<&Designation>), G::Target>,
+    level: Res<GameLevel>,
+    mut lines: ResMut<DebugLines>,
+    mut commands: Commands,
+) {
+    let Ok((action_state, camera, camera_transform)) = control.get_single() else {
+        return;
+    };
+
+    if action_state.just_pressed(ControlAction::Deselect)
+        || action_state.just_pressed(ControlAction::Interact)
+    {
+        commands.remove_resource::<PaintingDesignation>();
+        return;
+    }
+
+    let hovered = windows
+        .get_single()
+        .ok()
+        .and_then(|w| w.cursor_position())
+        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
+        .and_then(|ray| {
+            ray.intersect_plane(Vec3::ZERO, Vec3::Y)
+                .map(|distance| level.get_tile_at(ray.get_point(distance)))
+        });
+    let Some(hovered) = hovered else {
+        return;
+    };
+
+    if painting.start.is_none()
+        && action_state.just_pressed(ControlAction::Select)
+        && ui_buttons.iter().all(|i| *i == Interaction::None)
+    {
+        painting.start = Some(hovered);
+    }
+
+    let area = area(painting.start.unwrap_or(hovered), hovered);
+    draw_area_grid(&mut lines, area);
+
+    if painting.start.is_none() || action_state.pressed(ControlAction::Select) {
+        return;
+    }
+
+    painting.start = None;
+
+    for (entity, transform, designation) in targets.iter() {
+        if designation.is_some_and(|d| d.is_errand::<G::Errand>()) {
+            continue;
+        }
+
+        if is_in_area(area, level.get_tile_at(transform.translation())) {
+            commands
+                .entity(entity)
+                .insert(Designation::new(entity, G::create_errand(entity)));
+        }
+    }
+}
+
+pub trait AreaDesignationGizmoAppExtension {
+    fn add_area_designation_gizmo<G: AreaDesignationGizmo + 'static>(&mut self) -> &mut Self;
+}
+
+impl AreaDesignationGizmoAppExtension for App {
+    fn add_area_designation_gizmo<G: AreaDesignationGizmo + 'static>(&mut self) -> &mut Self {
+        add_base_gizmo_systems::<AreaGizmo<G>>(self);
+        self.add_systems(
+            Update,
+            (
+                start_painting_when_gizmo_clicked::<G>,
+                paint_designation::<G>.run_if(is_painting::<G>),
+            ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn area_includes_both_corners_in_any_direction() {
+        let area = area(GridPosition::new(3, 1), GridPosition::new(1, 2));
+
+        assert!(is_in_area(area, GridPosition::new(1, 1)));
+        assert!(is_in_area(area, GridPosition::new(3, 2)));
+        assert!(is_in_area(area, GridPosition::new(2, 2)));
+        assert!(!is_in_area(area, GridPosition::new(0, 1)));
+        assert!(!is_in_area(area, GridPosition::new(2, 3)));
+    }
+}
@@ -1,3 +1,4 @@
+mod area_designation_gizmo;
 mod designation_gizmo;
 mod menu_gizmo;
 mod button_gizmo;
@@ -6,6 +7,7 @@ use crate::prelude::*;
 use crate::{has_any_query_matches, GameState};
 use bevy_ecs::query::{ReadOnlyWorldQuery, WorldQuery};
 use std::marker::PhantomData;
+pub use area_designation_gizmo::*;
 pub use designation_gizmo::*;
 pub use menu_gizmo::*;
 pub use button_gizmo::*;
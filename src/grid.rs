@@ -1,7 +1,8 @@
 use std::fmt::{Debug, Formatter};
 use prettytable::{Row, Table, Cell};
+use serde::{Deserialize, Serialize};
 
-#[derive(Eq, PartialEq, Clone)]
+#[derive(Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Grid<T> {
     items: Vec<T>,
     height: i32,
@@ -104,7 +105,21 @@ impl<T> Grid<T>
     }
 }
 
-#[derive(Eq, PartialEq, Hash, Debug, Clone, Copy)]
+impl Grid<bool> {
+    /// Lays out a grid in tests, one string per row with `o` for open tiles.
+    #[cfg(test)]
+    pub fn from_rows(rows: &[&str]) -> Self {
+        let width = rows.first().map_or(0, |row| row.len() as i32);
+        let open = rows
+            .iter()
+            .flat_map(|row| row.chars().map(|c| c == 'o'))
+            .collect();
+
+        Grid::new_from_list(width, rows.len() as i32, open)
+    }
+}
+
+#[derive(Serialize, Deserialize, Eq, PartialEq, Hash, Debug, Clone, Copy)]
 pub struct GridPosition {
     pub x: i32,
     pub z: i32,
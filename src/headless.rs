@@ -0,0 +1,163 @@
+//! Runs the game logic without a window, GPU or asset files, so scripted scenarios can be
+//! played out in tests.
+
+use crate::camera_control::InteractedWith;
+use crate::errands::demolish_building_errand::DemolishAssets;
+use crate::errands::ErrandsPlugin;
+use crate::game_level::{GameLevel, TILE_SIZE};
+use crate::game_level_render::{GameLevelRenderPlugin, WorldTileTracker};
+use crate::grid::GridPosition;
+use crate::health::HealthPlugin;
+use crate::prelude::*;
+use crate::resource_items::ResourceItemsPlugin;
+use crate::stockpile::Stockpile;
+use crate::test_app::{TestAppExtensions, TEST_TIMESTEP};
+use crate::{nav_mesh_settings, spawn_raider, MyAssets};
+use bevy::ecs::system::CommandQueue;
+use bevy::prelude::shape::Box;
+use bevy::scene::ScenePlugin;
+use oxidized_navigation::OxidizedNavigationPlugin;
+
+pub fn headless_app(level: GameLevel) -> App {
+    let mut app = App::new();
+
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        HierarchyPlugin,
+        AssetPlugin::default(),
+        ScenePlugin,
+    ))
+    .add_asset::<Mesh>()
+    .add_asset::<StandardMaterial>()
+    .use_test_timestep()
+    .add_plugins(RapierPhysicsPlugin::<NoUserData>::default())
+    .insert_resource(RapierConfiguration {
+        timestep_mode: TimestepMode::Fixed {
+            dt: TEST_TIMESTEP,
+            substeps: 1,
+        },
+        ..default()
+    })
+    .add_plugins(OxidizedNavigationPlugin {
+        settings: nav_mesh_settings(),
+    })
+    .add_state::<GameState>()
+    .add_event::<InteractedWith>()
+    .init_resource::<Stockpile>()
+    .add_plugins((
+        ErrandsPlugin,
+        HealthPlugin,
+        GameLevelRenderPlugin,
+        ResourceItemsPlugin,
+    ));
+
+    let my_assets = stub_assets(&mut app.world);
+    app.insert_resource(my_assets)
+        .init_resource::<DemolishAssets>()
+        .insert_resource(level)
+        .insert_resource(NextState(Some(GameState::Playing)));
+
+    app
+}
+
+/// Plain boxes stand in for the wall meshes, so walls still get colliders that fill their
+/// tile. Everything that is only ever rendered keeps a default handle.
+fn stub_assets(world: &mut World) -> MyAssets {
+    let wall = world
+        .resource_mut::<Assets<Mesh>>()
+        .add(Box::new(TILE_SIZE, TILE_SIZE, TILE_SIZE).into());
+
+    MyAssets {
+        full_wall_mesh: wall.clone(),
+        three_way_wall_mesh: wall.clone(),
+        outer_corner_wall_mesh: wall.clone(),
+        inner_corner_wall_mesh: wall.clone(),
+        inner_diagonal_wall_mesh: wall,
+        wall_material: Handle::default(),
+        floor: Handle::default(),
+        raider: Handle::default(),
+        mine_wall_icon: Handle::default(),
+        ore_model: Handle::default(),
+    }
+}
+
+pub trait HeadlessAppExtensions {
+    /// Steps until the map of the level has been spawned.
+    fn spawn_map(&mut self);
+    fn wall_at(&self, position: GridPosition) -> Option<Entity>;
+    fn spawn_raider_at(&mut self, translation: Vec3) -> Entity;
+}
+
+impl HeadlessAppExtensions for App {
+    fn spawn_map(&mut self) {
+        for _ in 0..10 {
+            if !self.world.resource::<WorldTileTracker>().is_empty() {
+                return;
+            }
+            self.update();
+        }
+
+        panic!("Map was not spawned");
+    }
+
+    fn wall_at(&self, position: GridPosition) -> Option<Entity> {
+        self.world.resource::<WorldTileTracker>().wall_at(position)
+    }
+
+    fn spawn_raider_at(&mut self, translation: Vec3) -> Entity {
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, &self.world);
+        let raider = spawn_raider(
+            &mut commands,
+            self.world.resource::<MyAssets>(),
+            format!("Raider {}", translation),
+            Transform::from_translation(translation),
+        );
+        queue.apply(&mut self.world);
+
+        raider
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errands::MineWallErrand;
+    use crate::game_level::WallType;
+    use crate::grid::Grid;
+    use crate::resource_items::ResourceItem;
+
+    #[test]
+    fn two_raiders_mine_a_wall_and_the_level_opens_up() {
+        let mut carved = Grid::new(6, 3, false);
+        carved.set(1, 1, true);
+        carved.set(2, 1, true);
+        carved.set(4, 1, true);
+        let mut level = GameLevel::new_from_carved_tiles(carved, &[GridPosition::new(1, 1)]);
+        level.set_wall_type(3, 1, WallType::LooseRock);
+
+        let mut app = headless_app(level);
+        app.spawn_map();
+
+        let wall = app.wall_at(GridPosition::new(3, 1)).expect("Wall should exist");
+        for z in [13.0, 17.0] {
+            let raider = app.spawn_raider_at(Vec3::new(28.0, 3.2, z));
+            app.world
+                .get_mut::<ErrandQueue>(raider)
+                .unwrap()
+                .append_independent_errand(MineWallErrand::new(wall));
+        }
+
+        // Loose rock takes five seconds for a single miner.
+        app.step((3.0 / TEST_TIMESTEP) as usize);
+
+        let level = app.world.resource::<GameLevel>();
+        assert!(level.is_open(3, 1));
+        assert!(level.is_open(4, 1), "the cavern behind the wall should open up");
+        assert!(app.world.get_entity(wall).is_none());
+
+        let mut items = app.world.query::<&ResourceItem>();
+        assert_eq!(items.iter(&app.world).count(), 1);
+    }
+}
@@ -0,0 +1,185 @@
+use crate::buildings::{BuildingFootprint, BuildingTypes, ConstructionSite};
+use crate::errands::{Designation, IsWorking};
+use crate::game_level::{GameLevel, ResourceType};
+use crate::game_level_render::WorldTileTracker;
+use crate::health::Health;
+use crate::prelude::*;
+
+/// Describes the selection to the player: what it is, how healthy it is and what is happening
+/// to it.
+pub struct InfoPanelPlugin;
+
+impl Plugin for InfoPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_info_panel)
+            .add_systems(Update, update_info_panel);
+    }
+}
+
+#[derive(Component)]
+struct InfoPanel;
+
+type SelectedInfo<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        Option<&'static Name>,
+        Option<&'static Health>,
+        Option<&'static ErrandQueue>,
+        Option<&'static IsWorking>,
+        Option<&'static ConstructionSite>,
+        Option<&'static BuildingFootprint>,
+        Option<&'static Designation>,
+    ),
+    With<Selected>,
+>;
+
+fn spawn_info_panel(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_background_color(Color::BLACK.with_a(0.5))
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            right: Val::Px(5.),
+            bottom: Val::Px(5.),
+            padding: UiRect::all(Val::Px(5.)),
+            display: Display::None,
+            ..default()
+        }),
+        InfoPanel,
+    ));
+}
+
+fn format_cost(cost: &[(ResourceType, u32)]) -> String {
+    if cost.is_empty() {
+        return "Free".to_string();
+    }
+
+    cost.iter()
+        .map(|(resource_type, amount)| format!("{} {:?}", amount, resource_type))
+        .join(", ")
+}
+
+fn percent(fraction: f32) -> String {
+    format!("{:.0}%", fraction.clamp(0.0, 1.0) * 100.)
+}
+
+fn update_info_panel(
+    selected: SelectedInfo,
+    level: Option<Res<GameLevel>>,
+    tracker: Res<WorldTileTracker>,
+    building_types: Res<BuildingTypes>,
+    mut panel: Query<(&mut Text, &mut Style), With<InfoPanel>>,
+) {
+    let mut value = String::new();
+    let count = selected.iter().len();
+
+    // Many walls are easily selected at once, so only the first one is described.
+    if let Some((entity, name, health, queue, working, site, footprint, designation)) =
+        selected.iter().min_by_key(|(entity, ..)| *entity)
+    {
+        match name {
+            Some(name) => value.push_str(name.as_str()),
+            None => value.push_str(&format!("{:?}", entity)),
+        }
+        if count > 1 {
+            value.push_str(&format!(" (+{} more)", count - 1));
+        }
+        value.push('\n');
+
+        let wall = tracker
+            .wall_position(entity)
+            .zip(level.as_ref())
+            .map(|(position, level)| level.wall_type(position.x, position.z));
+
+        if let Some(health) = health.filter(|_| wall.is_none()) {
+            value.push_str(&format!("Health: {:.1}/{:.1}\n", health.current, health.max));
+        }
+
+        if let Some(queue) = queue {
+            let active = working.and_then(|_| queue.iter().next());
+            match active {
+                Some(errand) => value.push_str(&format!("Working on: {}\n", errand.name())),
+                None => value.push_str("Idle\n"),
+            }
+            value.push_str(&format!("Queued errands: {}\n", queue.len()));
+        }
+
+        if let Some(site) = site {
+            let building = site.building();
+            if site.has_all_materials() {
+                value.push_str(&format!(
+                    "Status: Under construction, {}\n",
+                    percent(site.progress() / building.get_build_time())
+                ));
+            } else {
+                value.push_str("Status: Waiting for materials\n");
+            }
+            value.push_str(&format!("Cost: {}\n", format_cost(&building.get_cost())));
+            value.push_str(&format!(
+                "Delivered: {}/{}\n",
+                site.delivered().len(),
+                site.delivered().len() + site.missing_materials(&[]).len()
+            ));
+        } else if footprint.is_some() {
+            value.push_str("Status: Complete\n");
+            if let Some(building) = name.and_then(|n| building_types.get(n.as_str())) {
+                value.push_str(&format!("Cost: {}\n", format_cost(&building.get_cost())));
+            }
+        }
+
+        if let Some(wall_type) = wall {
+            value.push_str(&format!("Wall type: {:?}\n", wall_type));
+            match health {
+                Some(health) => value.push_str(&format!(
+                    "Mined: {}\n",
+                    percent(1.0 - health.current / health.max)
+                )),
+                None => value.push_str("Cannot be mined\n"),
+            }
+        }
+
+        if let Some(designation) = designation {
+            value.push_str(&format!("Designated: {}\n", designation.name()));
+        }
+    }
+
+    let value = value.trim_end();
+
+    for (mut text, mut style) in panel.iter_mut() {
+        let display = if value.is_empty() {
+            Display::None
+        } else {
+            Display::Flex
+        };
+
+        if text.sections[0].value != value {
+            text.sections[0].value = value.to_string();
+        }
+        if style.display != display {
+            style.display = display;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cost_lists_every_resource() {
+        assert_eq!(
+            format_cost(&[(ResourceType::Ore, 2), (ResourceType::Crystal, 1)]),
+            "2 Ore, 1 Crystal"
+        );
+        assert_eq!(format_cost(&[]), "Free");
+    }
+}
@@ -0,0 +1,516 @@
+use crate::game_level::{GameLevel, ResourceType, Seam, WallType};
+use crate::grid::{Grid, GridPosition};
+use crate::prelude::*;
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::reflect::{TypePath, TypeUuid};
+use bevy::utils::BoxedFuture;
+use bevy_asset_loader::dynamic_asset::{DynamicAsset, DynamicAssetType, DynamicAssets};
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+
+pub const LEVEL_MAP_VERSION: u32 = 1;
+pub const LEVEL_MAP_SCHEMA_PATH: &str = "assets/levels/level.schema.json";
+
+pub struct LevelMapPlugin;
+
+impl Plugin for LevelMapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<LevelMap>()
+            .add_asset_loader(LevelMapLoader)
+            .init_resource::<SelectedLevel>()
+            .add_systems(Startup, register_selected_level)
+            .load_assets::<LevelAssets>();
+    }
+}
+
+/// A level as described by a `.level.json` map file.
+#[derive(Serialize, Deserialize, JsonSchema, TypeUuid, TypePath, Debug, Clone)]
+#[uuid = "5d2b6c8e-3f0a-4c55-9a43-7e8d1b9f2a61"]
+#[serde(deny_unknown_fields)]
+pub struct LevelMap {
+    #[serde(rename = "$schema", default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    /// The version of the map format. Must match the version supported by the game.
+    pub version: u32,
+    /// Number of tiles along the x axis.
+    pub width: i32,
+    /// Number of tiles along the z axis.
+    pub height: i32,
+    /// The wall type used for every walled tile not listed in `walls`.
+    #[serde(default)]
+    pub default_wall: WallType,
+    /// Areas carved out of the rock. Everything else is walled. Caverns that are not
+    /// connected to a raider spawn stay hidden until a wall leading to them is mined.
+    pub open_tiles: Vec<TileArea>,
+    /// Areas of walls that are not made of the default wall type.
+    #[serde(default)]
+    pub walls: Vec<WallArea>,
+    /// Walls containing ore or energy crystals.
+    #[serde(default)]
+    pub seams: Vec<SeamArea>,
+    /// The tiles raiders are spawned on when the level starts.
+    pub raider_spawns: Vec<TilePosition>,
+    /// Tiles with a depot already built on them when the level starts.
+    #[serde(default)]
+    pub depots: Vec<TilePosition>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct TilePosition {
+    pub x: i32,
+    pub z: i32,
+}
+
+impl From<TilePosition> for GridPosition {
+    fn from(value: TilePosition) -> Self {
+        GridPosition::new(value.x, value.z)
+    }
+}
+
+/// A rectangle of tiles starting at `x`, `z`.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct TileArea {
+    pub x: i32,
+    pub z: i32,
+    #[serde(default = "one")]
+    pub width: i32,
+    #[serde(default = "one")]
+    pub height: i32,
+}
+
+fn one() -> i32 {
+    1
+}
+
+impl TileArea {
+    fn positions(&self) -> impl Iterator<Item = GridPosition> {
+        let (x, z, height) = (self.x, self.z, self.height);
+        (x..x + self.width).flat_map(move |x| (z..z + height).map(move |z| GridPosition::new(x, z)))
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy)]
+pub struct WallArea {
+    #[serde(flatten)]
+    pub area: TileArea,
+    #[serde(rename = "type")]
+    pub wall_type: WallType,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy)]
+pub struct SeamArea {
+    #[serde(flatten)]
+    pub area: TileArea,
+    pub resource: ResourceType,
+    /// How many resources each wall in the area drops when mined.
+    #[serde(rename = "yield", default = "default_seam_yield")]
+    pub yield_count: u32,
+}
+
+fn default_seam_yield() -> u32 {
+    3
+}
+
+impl LevelMap {
+    pub fn from_json(bytes: &[u8]) -> Result<Self> {
+        let map: LevelMap = serde_json::from_slice(bytes)?;
+        map.validate()?;
+        Ok(map)
+    }
+
+    pub fn json_schema() -> Result<String> {
+        let schema = schema_for!(LevelMap);
+        Ok(serde_json::to_string_pretty(&schema)?)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.version != LEVEL_MAP_VERSION {
+            return Err(anyhow!(
+                "Unsupported map version {}, expected {}",
+                self.version,
+                LEVEL_MAP_VERSION
+            ));
+        }
+
+        if self.width <= 0 || self.height <= 0 {
+            return Err(anyhow!(
+                "Map dimensions must be positive, got {}x{}",
+                self.width,
+                self.height
+            ));
+        }
+
+        for area in self
+            .open_tiles
+            .iter()
+            .chain(self.walls.iter().map(|w| &w.area))
+            .chain(self.seams.iter().map(|s| &s.area))
+        {
+            self.validate_area(area)?;
+        }
+
+        let level = self.build_carved_tiles();
+
+        for wall in self.walls.iter() {
+            if let Some(open) = wall
+                .area
+                .positions()
+                .find(|p| *level.get(p.x, p.z).unwrap())
+            {
+                return Err(anyhow!("Wall at {} is placed on a carved out tile", open));
+            }
+        }
+
+        for seam in self.seams.iter() {
+            for pos in seam.area.positions() {
+                if *level.get(pos.x, pos.z).unwrap() {
+                    return Err(anyhow!("Seam at {} is placed on a carved out tile", pos));
+                }
+
+                if self.wall_type_at(pos).mining_time().is_none() {
+                    return Err(anyhow!("Seam at {} is placed in a wall that can't be mined", pos));
+                }
+            }
+        }
+
+        if self.raider_spawns.is_empty() {
+            return Err(anyhow!("Map has no raider spawn points"));
+        }
+
+        for spawn in self.raider_spawns.iter() {
+            if !*level.get(spawn.x, spawn.z).unwrap_or(&false) {
+                return Err(anyhow!(
+                    "Raider spawn point {} is not on a carved out tile",
+                    GridPosition::from(*spawn)
+                ));
+            }
+        }
+
+        for depot in self.depots.iter() {
+            if !*level.get(depot.x, depot.z).unwrap_or(&false) {
+                return Err(anyhow!(
+                    "Depot at {} is not on a carved out tile",
+                    GridPosition::from(*depot)
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_area(&self, area: &TileArea) -> Result<()> {
+        if area.width <= 0 || area.height <= 0 {
+            return Err(anyhow!(
+                "Area at {} must have a positive size, got {}x{}",
+                GridPosition::new(area.x, area.z),
+                area.width,
+                area.height
+            ));
+        }
+
+        if area.x < 0
+            || area.z < 0
+            || area.x + area.width > self.width
+            || area.z + area.height > self.height
+        {
+            return Err(anyhow!(
+                "Area at {} with size {}x{} is outside the {}x{} map",
+                GridPosition::new(area.x, area.z),
+                area.width,
+                area.height,
+                self.width,
+                self.height
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn build_carved_tiles(&self) -> Grid<bool> {
+        let mut open_tiles = Grid::new(self.width, self.height, false);
+
+        for pos in self.open_tiles.iter().flat_map(|a| a.positions()) {
+            open_tiles.set(pos.x, pos.z, true);
+        }
+
+        open_tiles
+    }
+
+    fn wall_type_at(&self, pos: GridPosition) -> WallType {
+        self.walls
+            .iter()
+            .rev()
+            .find(|w| w.area.positions().contains(&pos))
+            .map(|w| w.wall_type)
+            .unwrap_or(self.default_wall)
+    }
+
+    pub fn build_level(&self) -> Result<GameLevel> {
+        self.validate()?;
+
+        let spawns = self.raider_spawn_positions().collect_vec();
+        let mut level = GameLevel::new_from_carved_tiles(self.build_carved_tiles(), &spawns);
+
+        for pos in level.iter_tiles().collect_vec() {
+            level.set_wall_type(pos.x, pos.z, self.default_wall);
+        }
+
+        for wall in self.walls.iter() {
+            for pos in wall.area.positions() {
+                level.set_wall_type(pos.x, pos.z, wall.wall_type);
+            }
+        }
+
+        for seam in self.seams.iter() {
+            for pos in seam.area.positions() {
+                level.set_seam(
+                    pos.x,
+                    pos.z,
+                    Some(Seam {
+                        resource_type: seam.resource,
+                        yield_count: seam.yield_count,
+                    }),
+                );
+            }
+        }
+
+        Ok(level)
+    }
+
+    pub fn raider_spawn_positions(&self) -> impl Iterator<Item = GridPosition> + '_ {
+        self.raider_spawns.iter().map(|s| GridPosition::from(*s))
+    }
+
+    pub fn depot_positions(&self) -> impl Iterator<Item = GridPosition> + '_ {
+        self.depots.iter().map(|d| GridPosition::from(*d))
+    }
+}
+
+struct LevelMapLoader;
+
+impl AssetLoader for LevelMapLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let map = LevelMap::from_json(bytes)
+                .map_err(|e| e.context(format!("Invalid level map {:?}", load_context.path())))?;
+            load_context.set_default_asset(LoadedAsset::new(map));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.json"]
+    }
+}
+
+/// The path of the map file to load, relative to the assets folder.
+#[derive(Resource)]
+pub struct SelectedLevel(pub String);
+
+impl Default for SelectedLevel {
+    fn default() -> Self {
+        Self("levels/ring.level.json".to_string())
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct LevelAssets {
+    #[asset(key = "level")]
+    pub map: Handle<LevelMap>,
+}
+
+#[derive(Debug)]
+struct LevelMapFile {
+    path: String,
+}
+
+impl DynamicAsset for LevelMapFile {
+    fn load(&self, asset_server: &AssetServer) -> Vec<HandleUntyped> {
+        vec![asset_server.load_untyped(&self.path)]
+    }
+
+    fn build(&self, world: &mut World) -> Result<DynamicAssetType> {
+        let asset_server = world.resource::<AssetServer>();
+        Ok(DynamicAssetType::Single(
+            asset_server.get_handle_untyped(&self.path),
+        ))
+    }
+}
+
+fn register_selected_level(
+    selected: Res<SelectedLevel>,
+    mut dynamic_assets: ResMut<DynamicAssets>,
+) {
+    info!("Loading level {}", selected.0);
+    dynamic_assets.register_asset(
+        "level",
+        Box::new(LevelMapFile {
+            path: selected.0.clone(),
+        }),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> Result<LevelMap> {
+        LevelMap::from_json(json.as_bytes())
+    }
+
+    #[test]
+    fn bundled_maps_are_valid() {
+        let ring = include_str!("../assets/levels/ring.level.json");
+
+        let level = parse(ring).unwrap().build_level().unwrap();
+
+        assert_eq!(level.width(), 10);
+        assert_eq!(level.height(), 10);
+        assert!(level.is_open(1, 1));
+        assert!(level.is_open(9, 5));
+        assert!(!level.is_open(5, 5));
+        assert!(!level.is_discovered(5, 5));
+    }
+
+    #[test]
+    fn schema_is_up_to_date() {
+        let committed = include_str!("../assets/levels/level.schema.json");
+
+        assert_eq!(
+            committed.trim(),
+            LevelMap::json_schema().unwrap(),
+            "Level schema is outdated, regenerate it with `cargo run -- --write-level-schema`"
+        );
+    }
+
+    #[test]
+    fn applies_wall_types() {
+        let map = parse(
+            r#"{
+                "version": 1,
+                "width": 3,
+                "height": 3,
+                "default_wall": "loose_rock",
+                "open_tiles": [{ "x": 1, "z": 1 }],
+                "walls": [{ "x": 0, "z": 0, "width": 3, "type": "solid_rock" }],
+                "raider_spawns": [{ "x": 1, "z": 1 }]
+            }"#,
+        )
+        .unwrap();
+
+        let level = map.build_level().unwrap();
+
+        assert_eq!(level.wall_type(1, 0), WallType::SolidRock);
+        assert_eq!(level.wall_type(0, 2), WallType::LooseRock);
+    }
+
+    #[test]
+    fn applies_seams() {
+        let map = parse(
+            r#"{
+                "version": 1,
+                "width": 3,
+                "height": 3,
+                "open_tiles": [{ "x": 1, "z": 1 }],
+                "seams": [{ "x": 0, "z": 1, "resource": "crystal", "yield": 5 }],
+                "raider_spawns": [{ "x": 1, "z": 1 }]
+            }"#,
+        )
+        .unwrap();
+
+        let level = map.build_level().unwrap();
+
+        assert_eq!(
+            level.seam(0, 1),
+            Some(Seam {
+                resource_type: ResourceType::Crystal,
+                yield_count: 5
+            })
+        );
+        assert_eq!(level.seam(0, 0), None);
+    }
+
+    #[test]
+    fn rejects_seam_in_solid_rock() {
+        let result = parse(
+            r#"{
+                "version": 1,
+                "width": 3,
+                "height": 3,
+                "open_tiles": [{ "x": 1, "z": 1 }],
+                "walls": [{ "x": 0, "z": 0, "type": "solid_rock" }],
+                "seams": [{ "x": 0, "z": 0, "resource": "ore" }],
+                "raider_spawns": [{ "x": 1, "z": 1 }]
+            }"#,
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn rejects_unsupported_version() {
+        let result = parse(
+            r#"{
+                "version": 999,
+                "width": 3,
+                "height": 3,
+                "open_tiles": [{ "x": 1, "z": 1 }],
+                "raider_spawns": [{ "x": 1, "z": 1 }]
+            }"#,
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn rejects_areas_outside_map() {
+        let result = parse(
+            r#"{
+                "version": 1,
+                "width": 3,
+                "height": 3,
+                "open_tiles": [{ "x": 1, "z": 1, "width": 5 }],
+                "raider_spawns": [{ "x": 1, "z": 1 }]
+            }"#,
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn rejects_spawn_in_wall() {
+        let result = parse(
+            r#"{
+                "version": 1,
+                "width": 3,
+                "height": 3,
+                "open_tiles": [{ "x": 1, "z": 1 }],
+                "raider_spawns": [{ "x": 0, "z": 0 }]
+            }"#,
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn rejects_depot_in_wall() {
+        let result = parse(
+            r#"{
+                "version": 1,
+                "width": 3,
+                "height": 3,
+                "open_tiles": [{ "x": 1, "z": 1 }],
+                "raider_spawns": [{ "x": 1, "z": 1 }],
+                "depots": [{ "x": 2, "z": 1 }]
+            }"#,
+        );
+
+        assert!(result.is_err());
+    }
+}
@@ -1,27 +1,47 @@
 mod buildings;
 mod camera_control;
+mod cave_generator;
 mod debug_text;
 mod errands;
 mod game_level;
 mod game_level_render;
 mod gizmos;
 mod grid;
+mod level_map;
+mod mining_effects;
 mod nav_mesh_debug;
 mod prelude;
+mod priority_panel;
+mod queue_display;
 mod ray_hit_helpers;
+mod resource_items;
+mod save_game;
 mod selection;
+mod stockpile;
 mod health;
+mod info_panel;
+#[cfg(test)]
+mod headless;
+#[cfg(test)]
+mod test_app;
 
-use crate::buildings::BuildingsPlugin;
+use crate::buildings::{BuildingsPlugin, DepotAssets};
 use crate::camera_control::CameraControlPlugin;
+use crate::cave_generator::{generate_cave, CaveParameters, Skirmish};
 use crate::debug_text::DebugTextPlugin;
-use crate::errands::{ErrandsPlugin, Miner, PlayerMovable, WorkerPriorities};
-use crate::game_level::GameLevel;
+use crate::errands::{Builder, ErrandsPlugin, Hauler, Miner, PlayerMovable, WorkerPriorities};
 use crate::game_level_render::GameLevelRenderPlugin;
 use crate::gizmos::GizmosPlugin;
+use crate::level_map::{LevelAssets, LevelMap, LevelMapPlugin, SelectedLevel, LEVEL_MAP_SCHEMA_PATH};
+use crate::mining_effects::MiningEffectsPlugin;
 use crate::nav_mesh_debug::NavMeshDebugPlugin;
 use crate::prelude::*;
+use crate::priority_panel::PriorityPanelPlugin;
+use crate::queue_display::QueueDisplayPlugin;
+use crate::resource_items::ResourceItemsPlugin;
+use crate::save_game::SaveGamePlugin;
 use crate::selection::SelectionPlugin;
+use crate::stockpile::StockpilePlugin;
 use bevy::asset::ChangeWatcher;
 use bevy::pbr::wireframe::WireframePlugin;
 use bevy::window::ExitCondition;
@@ -32,10 +52,30 @@ use oxidized_navigation::{NavMeshSettings, OxidizedNavigationPlugin};
 use std::f32::consts::PI;
 use std::time::Duration;
 use crate::health::HealthPlugin;
+use crate::info_panel::InfoPanelPlugin;
 
 fn main() {
+    let args = std::env::args().collect_vec();
+
+    if args.iter().any(|a| a == "--write-level-schema") {
+        let schema = LevelMap::json_schema().expect("Failed to generate level schema");
+        std::fs::write(LEVEL_MAP_SCHEMA_PATH, schema + "\n").expect("Failed to write level schema");
+        return;
+    }
+
     let mut app = App::new();
 
+    if let Some(level) = args.iter().skip_while(|a| *a != "--level").nth(1) {
+        app.insert_resource(SelectedLevel(level.clone()));
+    }
+
+    if let Some(seed) = args.iter().skip_while(|a| *a != "--seed").nth(1) {
+        app.insert_resource(Skirmish {
+            seed: seed.parse().expect("Seed should be a number"),
+            parameters: CaveParameters::default(),
+        });
+    }
+
     app.add_plugins((
         DefaultPlugins
             .set(AssetPlugin {
@@ -56,23 +96,9 @@ fn main() {
         //     ..default()
         // })
         .add_plugins(DebugLinesPlugin::default())
+        .add_plugins(HanabiPlugin)
         .add_plugins(OxidizedNavigationPlugin {
-            settings: NavMeshSettings {
-                cell_width: 0.25,
-                cell_height: 0.1,
-                tile_width: 100,
-                world_half_extents: 250.0,
-                world_bottom_bound: -100.0,
-                max_traversable_slope_radians: (1_f32).to_radians(),
-                walkable_height: 20,
-                walkable_radius: 5,
-                step_height: 3,
-                min_region_area: 100,
-                merge_region_area: 500,
-                max_contour_simplification_error: 1.1,
-                max_edge_length: 80,
-                max_tile_generation_tasks: Some(9),
-            },
+            settings: nav_mesh_settings(),
         })
         .add_state::<GameState>()
         .add_loading_state(
@@ -88,12 +114,38 @@ fn main() {
             GizmosPlugin,
             BuildingsPlugin,
             HealthPlugin,
+            LevelMapPlugin,
+            ResourceItemsPlugin,
+            StockpilePlugin,
+            SaveGamePlugin,
+            PriorityPanelPlugin,
+            QueueDisplayPlugin,
         ))
+        .add_plugins((InfoPanelPlugin, MiningEffectsPlugin))
         .load_assets::<MyAssets>()
         .add_systems(OnEnter(GameState::Playing), spawn_world)
         .run();
 }
 
+pub fn nav_mesh_settings() -> NavMeshSettings {
+    NavMeshSettings {
+        cell_width: 0.25,
+        cell_height: 0.1,
+        tile_width: 100,
+        world_half_extents: 250.0,
+        world_bottom_bound: -100.0,
+        max_traversable_slope_radians: (1_f32).to_radians(),
+        walkable_height: 20,
+        walkable_radius: 5,
+        step_height: 3,
+        min_region_area: 100,
+        merge_region_area: 500,
+        max_contour_simplification_error: 1.1,
+        max_edge_length: 80,
+        max_tile_generation_tasks: Some(9),
+    }
+}
+
 #[derive(AssetCollection, Resource)]
 pub struct MyAssets {
     #[asset(path = "wall.gltf#Mesh4/Primitive0")]
@@ -127,41 +179,52 @@ pub struct MyAssets {
     pub ore_model: Handle<Scene>,
 }
 
-fn spawn_world(mut commands: Commands, my_assets: Res<MyAssets>) {
-    let mut level = GameLevel::new(10, 10);
+fn spawn_world(
+    mut commands: Commands,
+    my_assets: Res<MyAssets>,
+    depot_assets: Res<DepotAssets>,
+    level_assets: Res<LevelAssets>,
+    maps: Res<Assets<LevelMap>>,
+    skirmish: Option<Res<Skirmish>>,
+) {
+    let (level, spawns, depots) = if let Some(skirmish) = skirmish {
+        info!("Generating skirmish cave from seed {}", skirmish.seed);
+        let cave = generate_cave(skirmish.seed, &skirmish.parameters);
+        (cave.level, cave.raider_spawns, vec![cave.depot])
+    } else {
+        let map = maps
+            .get(&level_assets.map)
+            .expect("Level map should be loaded before playing");
+
+        // The loader rejects invalid maps, so there is no playable state to fall back to here.
+        let level = map
+            .build_level()
+            .expect("Level map should have been validated when it was loaded");
 
-    for x in 1..=9 {
-        level.remove_wall(x, 1);
-        level.remove_wall(x, 9);
+        (
+            level,
+            map.raider_spawn_positions().collect_vec(),
+            map.depot_positions().collect_vec(),
+        )
+    };
+
+    for (i, spawn) in spawns.into_iter().enumerate() {
+        let position = level.get_position_at(spawn);
+
+        spawn_raider(
+            &mut commands,
+            &my_assets,
+            format!("Raider{i}"),
+            Transform::from_xyz(position.x, 3.2, position.z),
+        );
     }
 
-    for z in 1..=9 {
-        level.remove_wall(1, z);
-        level.remove_wall(9, z);
+    for depot in depots {
+        depot_assets.spawn_depot(&mut commands, &level, depot);
     }
 
     commands.insert_resource(level);
 
-    for i in 0..2 {
-        commands.spawn((
-            SceneBundle {
-                scene: my_assets.raider.clone(),
-                transform: Transform::from_xyz(15.0, 3.2, 15.0 + i as f32 * 10.),
-                ..default()
-            },
-            Collider::cuboid(0.6, 3., 0.4),
-            Name::new(format!("Raider{i}")),
-            RigidBody::KinematicVelocityBased,
-            LockedAxes::ROTATION_LOCKED_X | LockedAxes::ROTATION_LOCKED_Z,
-            ErrandQueue::new(),
-            KinematicCharacterController::default(),
-            Selectable::default(),
-            PlayerMovable,
-            Miner,
-            WorkerPriorities::default(),
-        ));
-    }
-
     // light
     commands.spawn(DirectionalLightBundle {
         directional_light: DirectionalLight {
@@ -178,6 +241,35 @@ fn spawn_world(mut commands: Commands, my_assets: Res<MyAssets>) {
     });
 }
 
+pub fn spawn_raider(
+    commands: &mut Commands,
+    my_assets: &MyAssets,
+    name: String,
+    transform: Transform,
+) -> Entity {
+    commands
+        .spawn((
+            SceneBundle {
+                scene: my_assets.raider.clone(),
+                transform,
+                ..default()
+            },
+            Collider::cuboid(0.6, 3., 0.4),
+            Name::new(name),
+            RigidBody::KinematicVelocityBased,
+            LockedAxes::ROTATION_LOCKED_X | LockedAxes::ROTATION_LOCKED_Z,
+            ErrandQueue::new(),
+            KinematicCharacterController::default(),
+            Selectable::default(),
+            PlayerMovable,
+            Miner,
+            Hauler,
+            Builder,
+            WorkerPriorities::default(),
+        ))
+        .id()
+}
+
 #[derive(Clone, Eq, PartialEq, Debug, Hash, Default, States)]
 pub enum GameState {
     #[default]
@@ -0,0 +1,258 @@
+use crate::errands::Minable;
+use crate::game_level::{HALF_TILE_SIZE, TILE_SIZE};
+use crate::health::Health;
+use crate::prelude::*;
+use std::collections::HashMap;
+
+/// Shows walls being mined: a progress bar above them, cracks as their health falls and dust
+/// while a miner is working on them.
+pub struct MiningEffectsPlugin;
+
+impl Plugin for MiningEffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CrackedMaterials>()
+            .add_systems(Startup, create_mining_effect_assets)
+            .add_systems(
+                Update,
+                (
+                    start_mining_visuals,
+                    update_mining_visuals,
+                    update_mining_dust,
+                )
+                    .chain(),
+            );
+    }
+}
+
+const CRACK_STAGES: usize = 3;
+const BAR_WIDTH: f32 = TILE_SIZE * 0.8;
+const BAR_HEIGHT: f32 = HALF_TILE_SIZE + 0.5;
+/// How long the dust keeps coming after the last bit of mining.
+const DUST_LINGER_SECONDS: f32 = 0.2;
+
+#[derive(Resource)]
+struct MiningEffectAssets {
+    dust: Handle<EffectAsset>,
+    bar: Handle<Mesh>,
+    bar_background: Handle<StandardMaterial>,
+    bar_fill: Handle<StandardMaterial>,
+}
+
+/// Darkened copies of wall materials, one per crack stage.
+#[derive(Resource, Default)]
+struct CrackedMaterials(HashMap<(Handle<StandardMaterial>, usize), Handle<StandardMaterial>>);
+
+/// Added to a wall the first time it takes damage.
+#[derive(Component)]
+struct MiningVisuals {
+    fill: Entity,
+    dust: Entity,
+    original_material: Handle<StandardMaterial>,
+    crack_stage: usize,
+    last_mined: f32,
+}
+
+fn create_mining_effect_assets(
+    mut commands: Commands,
+    mut effects: ResMut<Assets<EffectAsset>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mut color = Gradient::new();
+    color.add_key(0.0, Vec4::new(0.6, 0.55, 0.5, 0.8));
+    color.add_key(1.0, Vec4::new(0.6, 0.55, 0.5, 0.0));
+
+    let writer = ExprWriter::new();
+    let init_age = SetAttributeModifier::new(Attribute::AGE, writer.lit(0.).expr());
+    let init_lifetime = SetAttributeModifier::new(Attribute::LIFETIME, writer.lit(1.).expr());
+    let init_position = SetPositionSphereModifier {
+        center: writer.lit(Vec3::ZERO).expr(),
+        radius: writer.lit(HALF_TILE_SIZE).expr(),
+        dimension: ShapeDimension::Surface,
+    };
+    let init_velocity = SetVelocitySphereModifier {
+        center: writer.lit(Vec3::ZERO).expr(),
+        speed: writer.lit(2.).expr(),
+    };
+    let gravity = AccelModifier::new(writer.lit(Vec3::Y * -3.).expr());
+
+    let spawner = Spawner::rate(40.0.into()).with_starts_active(false);
+    let dust = effects.add(
+        EffectAsset::new(1024, spawner, writer.finish())
+            .with_name("mining dust")
+            .init(init_position)
+            .init(init_velocity)
+            .init(init_age)
+            .init(init_lifetime)
+            .update(gravity)
+            .render(SizeOverLifetimeModifier {
+                gradient: Gradient::constant(Vec2::splat(0.3)),
+                screen_space_size: false,
+            })
+            .render(ColorOverLifetimeModifier { gradient: color }),
+    );
+
+    commands.insert_resource(MiningEffectAssets {
+        dust,
+        bar: meshes.add(shape::Box::new(BAR_WIDTH, 0.2, 0.6).into()),
+        bar_background: materials.add(StandardMaterial {
+            base_color: Color::BLACK.with_a(0.7),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        }),
+        bar_fill: materials.add(StandardMaterial {
+            base_color: Color::rgb(1.0, 0.8, 0.2),
+            unlit: true,
+            ..default()
+        }),
+    });
+}
+
+/// Walls crack a bit more every time another part of their health is gone.
+fn crack_stage(health: &Health) -> usize {
+    let mined = 1.0 - (health.current / health.max).clamp(0.0, 1.0);
+    ((mined * (CRACK_STAGES + 1) as f32) as usize).min(CRACK_STAGES)
+}
+
+fn start_mining_visuals(
+    walls: Query<
+        (Entity, &Health, &Transform, &Handle<StandardMaterial>),
+        (With<Minable>, Changed<Health>, Without<MiningVisuals>),
+    >,
+    assets: Res<MiningEffectAssets>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, health, transform, material) in walls.iter() {
+        // Walls that are out of health are despawned this frame.
+        if health.current >= health.max || health.current <= 0.0 {
+            continue;
+        }
+
+        let mut fill = None;
+        let mut dust = None;
+        commands.entity(entity).with_children(|wall| {
+            // Walls are rotated to fit their neighbours, the bar shouldn't be.
+            wall.spawn(SpatialBundle::from_transform(Transform {
+                translation: Vec3::Y * BAR_HEIGHT,
+                rotation: transform.rotation.inverse(),
+                ..default()
+            }))
+            .with_children(|bar| {
+                bar.spawn(PbrBundle {
+                    mesh: assets.bar.clone(),
+                    material: assets.bar_background.clone(),
+                    ..default()
+                });
+                fill = Some(
+                    bar.spawn(PbrBundle {
+                        mesh: assets.bar.clone(),
+                        material: assets.bar_fill.clone(),
+                        transform: Transform::from_scale(Vec3::new(0., 1., 1.)),
+                        ..default()
+                    })
+                    .id(),
+                );
+            });
+
+            dust = Some(
+                wall.spawn(ParticleEffectBundle::new(assets.dust.clone()))
+                    .id(),
+            );
+        });
+
+        if let (Some(fill), Some(dust)) = (fill, dust) {
+            commands.entity(entity).insert(MiningVisuals {
+                fill,
+                dust,
+                original_material: material.clone(),
+                crack_stage: 0,
+                last_mined: time.elapsed_seconds(),
+            });
+        }
+    }
+}
+
+fn update_mining_visuals(
+    mut walls: Query<
+        (&Health, &mut MiningVisuals, &mut Handle<StandardMaterial>),
+        (With<Minable>, Changed<Health>),
+    >,
+    mut fills: Query<&mut Transform>,
+    mut cracked: ResMut<CrackedMaterials>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    time: Res<Time>,
+) {
+    for (health, mut visuals, mut material) in walls.iter_mut() {
+        visuals.last_mined = time.elapsed_seconds();
+
+        let progress = 1.0 - (health.current / health.max).clamp(0.0, 1.0);
+        if let Ok(mut fill) = fills.get_mut(visuals.fill) {
+            // Grows from the left edge of the bar, slightly on top of the background.
+            fill.scale.x = progress;
+            fill.translation = Vec3::new(-BAR_WIDTH * (1.0 - progress) / 2., 0.01, 0.);
+        }
+
+        let stage = crack_stage(health);
+        if stage == visuals.crack_stage {
+            continue;
+        }
+        visuals.crack_stage = stage;
+
+        let original = visuals.original_material.clone();
+        *material = cracked
+            .0
+            .entry((original.clone(), stage))
+            .or_insert_with(|| {
+                let mut variant = materials.get(&original).cloned().unwrap_or_default();
+                let darken = 1.0 - 0.15 * stage as f32;
+                variant.base_color = Color::rgba(
+                    variant.base_color.r() * darken,
+                    variant.base_color.g() * darken,
+                    variant.base_color.b() * darken,
+                    variant.base_color.a(),
+                );
+                variant.perceptual_roughness = 1.0;
+                materials.add(variant)
+            })
+            .clone();
+    }
+}
+
+fn update_mining_dust(
+    walls: Query<&MiningVisuals>,
+    mut spawners: Query<&mut EffectSpawner>,
+    time: Res<Time>,
+) {
+    for visuals in walls.iter() {
+        let Ok(mut spawner) = spawners.get_mut(visuals.dust) else {
+            continue;
+        };
+
+        let active = time.elapsed_seconds() - visuals.last_mined < DUST_LINGER_SECONDS;
+        if spawner.is_active() != active {
+            spawner.set_active(active);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walls_crack_as_health_falls() {
+        let mut health = Health::new(8.0);
+        assert_eq!(crack_stage(&health), 0);
+
+        health.current = 5.0;
+        assert_eq!(crack_stage(&health), 1);
+
+        health.current = 0.5;
+        assert_eq!(crack_stage(&health), CRACK_STAGES);
+
+        health.current = -1.0;
+        assert_eq!(crack_stage(&health), CRACK_STAGES);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::errands::WorkerPriorities;
+use crate::prelude::*;
+use std::any::TypeId;
+
+pub struct PriorityPanelPlugin;
+
+impl Plugin for PriorityPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_priority_panel).add_systems(
+            Update,
+            (
+                apply_priority_buttons,
+                update_priority_panel.run_if(selected_priorities_changed),
+            )
+                .chain(),
+        );
+    }
+}
+
+const BUTTON_SIZE: f32 = 24.;
+const BUTTON_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+const ACTIVE_BUTTON_COLOR: Color = Color::rgb(0.8, 0.5, 0.1);
+
+/// Lists the capabilities of the selected raiders, so some can be dedicated to mining and
+/// others to hauling.
+#[derive(Component)]
+struct PriorityPanel;
+
+#[derive(Component)]
+struct PriorityButton {
+    errand_type_id: TypeId,
+    priority: Option<u8>,
+}
+
+fn spawn_priority_panel(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(5.),
+                bottom: Val::Px(5.),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(2.),
+                padding: UiRect::all(Val::Px(5.)),
+                display: Display::None,
+                ..default()
+            },
+            background_color: Color::BLACK.with_a(0.5).into(),
+            ..default()
+        },
+        PriorityPanel,
+    ));
+}
+
+fn selected_priorities_changed(
+    changed: Query<(), (With<Selected>, Changed<WorkerPriorities>)>,
+    selected: Query<(), Added<Selected>>,
+    deselected: RemovedComponents<Selected>,
+) -> bool {
+    !changed.is_empty() || !selected.is_empty() || !deselected.is_empty()
+}
+
+fn update_priority_panel(
+    selected: Query<&WorkerPriorities, With<Selected>>,
+    mut panel: Query<(Entity, &mut Style), With<PriorityPanel>>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let Ok((panel, mut style)) = panel.get_single_mut() else {
+        return;
+    };
+
+    let capabilities = selected
+        .iter()
+        .flat_map(|w| w.capabilities())
+        .unique_by(|p| p.errand_type_id())
+        .sorted_by_key(|p| p.errand_type_order())
+        .collect_vec();
+
+    style.display = if capabilities.is_empty() {
+        Display::None
+    } else {
+        Display::Flex
+    };
+
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+        font_size: 16.0,
+        color: Color::WHITE,
+    };
+
+    commands.entity(panel).despawn_descendants();
+    commands.entity(panel).with_children(|p| {
+        for capability in capabilities {
+            let errand_type_id = capability.errand_type_id();
+
+            // Only highlighted when all selected raiders agree.
+            let current = selected
+                .iter()
+                .filter_map(|w| w.capabilities().find(|c| c.errand_type_id() == errand_type_id))
+                .map(|c| c.priority())
+                .unique()
+                .exactly_one()
+                .ok();
+
+            p.spawn(NodeBundle {
+                style: Style {
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(2.),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|row| {
+                row.spawn(
+                    TextBundle::from_section(capability.name(), text_style.clone()).with_style(
+                        Style {
+                            width: Val::Px(80.),
+                            ..default()
+                        },
+                    ),
+                );
+
+                let options =
+                    std::iter::once(None).chain((1..=WorkerPriorities::LOWEST).map(Some));
+                for priority in options {
+                    let label = priority.map_or("Off".to_string(), |p| p.to_string());
+                    let color = if current == Some(priority) {
+                        ACTIVE_BUTTON_COLOR
+                    } else {
+                        BUTTON_COLOR
+                    };
+
+                    row.spawn((
+                        ButtonBundle {
+                            style: Style {
+                                min_width: Val::Px(BUTTON_SIZE),
+                                height: Val::Px(BUTTON_SIZE),
+                                padding: UiRect::horizontal(Val::Px(4.)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: color.into(),
+                            ..default()
+                        },
+                        PriorityButton {
+                            errand_type_id,
+                            priority,
+                        },
+                    ))
+                    .with_children(|button| {
+                        button.spawn(TextBundle::from_section(label, text_style.clone()));
+                    });
+                }
+            });
+        }
+    });
+}
+
+fn apply_priority_buttons(
+    buttons: Query<(&Interaction, &PriorityButton), Changed<Interaction>>,
+    mut selected: Query<&mut WorkerPriorities, With<Selected>>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        for mut priorities in selected.iter_mut() {
+            priorities.set_priority(button.errand_type_id, button.priority);
+        }
+    }
+}
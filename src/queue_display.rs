@@ -0,0 +1,314 @@
+use crate::buildings::ConstructionSite;
+use crate::camera_control::Selector;
+use crate::errands::{ErrandTarget, IsWorking, MoveToPosition, QueuedErrand, WorkingOnErrand};
+use crate::health::Health;
+use crate::prelude::*;
+use bevy_prototype_debug_lines::DebugLines;
+
+/// Shows what the selected raiders are going to do: their paths and queued errand targets in
+/// the world, and a list of their queues.
+pub struct QueueDisplayPlugin;
+
+impl Plugin for QueueDisplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_queue_list).add_systems(
+            Update,
+            (draw_queued_paths, update_queue_markers, update_queue_list),
+        );
+    }
+}
+
+const PATH_COLOR: Color = Color::rgb(0.2, 0.9, 0.3);
+const QUEUED_COLOR: Color = Color::rgb(0.9, 0.8, 0.2);
+const LINE_OFFSET: Vec3 = Vec3::new(0.0, 0.1, 0.0);
+const PROGRESS_BAR_OFFSET: Vec3 = Vec3::new(0.0, 4.0, 0.0);
+const PROGRESS_BAR_WIDTH: f32 = 40.;
+
+#[derive(Component)]
+struct QueueList;
+
+/// Numbered marker on a queued errand's target. Markers are reused from frame to frame and
+/// hidden while there are more than targets to mark.
+#[derive(Component)]
+struct QueueMarker;
+
+/// Shows how far along a raider is with its active errand. Reused like [`QueueMarker`].
+#[derive(Component)]
+struct ProgressBar {
+    fill: Entity,
+}
+
+type SelectedWorkers<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static GlobalTransform,
+        &'static ErrandQueue,
+        Option<&'static WorkingOnErrand<MoveToPosition>>,
+        Option<&'static Name>,
+    ),
+    With<Selected>,
+>;
+
+fn target_position(
+    errand: &dyn QueuedErrand,
+    transforms: &Query<&GlobalTransform>,
+) -> Option<Vec3> {
+    match errand.target()? {
+        ErrandTarget::Position(position) => Some(position),
+        ErrandTarget::Entity(entity) => transforms.get(entity).ok().map(|t| t.translation()),
+    }
+}
+
+fn draw_queued_paths(
+    selected: SelectedWorkers,
+    transforms: Query<&GlobalTransform>,
+    mut lines: ResMut<DebugLines>,
+) {
+    for (_, worker, queue, moving, _) in selected.iter() {
+        let mut last = worker.translation();
+
+        for (i, errand) in queue.iter().enumerate() {
+            let Some(target) = target_position(errand, &transforms) else {
+                continue;
+            };
+
+            match moving.filter(|m| i == 0 && m.id() == errand.id()) {
+                Some(moving) if !moving.remaining_path().is_empty() => {
+                    for point in moving.remaining_path() {
+                        lines.line_colored(last + LINE_OFFSET, *point + LINE_OFFSET, 0., PATH_COLOR);
+                        last = *point;
+                    }
+                }
+                _ => {
+                    lines.line_colored(last + LINE_OFFSET, target + LINE_OFFSET, 0., QUEUED_COLOR);
+                    last = target;
+                }
+            }
+        }
+    }
+}
+
+/// How far along the active errand is, judged by what it is working on.
+fn progress(
+    errand: &dyn QueuedErrand,
+    moving: Option<&WorkingOnErrand<MoveToPosition>>,
+    targets: &Query<(Option<&Health>, Option<&ConstructionSite>)>,
+) -> Option<f32> {
+    if let Some(moving) = moving.filter(|m| m.id() == errand.id()) {
+        return moving.progress();
+    }
+
+    let Some(ErrandTarget::Entity(target)) = errand.target() else {
+        return None;
+    };
+
+    match targets.get(target).ok()? {
+        (_, Some(site)) => Some(site.progress() / site.building().get_build_time()),
+        (Some(health), None) => Some(1.0 - health.current / health.max),
+        (None, None) => None,
+    }
+}
+
+fn marker_style(position: Vec2) -> Style {
+    Style {
+        position_type: PositionType::Absolute,
+        left: Val::Px(position.x),
+        top: Val::Px(position.y),
+        padding: UiRect::horizontal(Val::Px(4.)),
+        ..default()
+    }
+}
+
+fn bar_style(position: Vec2) -> Style {
+    Style {
+        position_type: PositionType::Absolute,
+        left: Val::Px(position.x - PROGRESS_BAR_WIDTH / 2.),
+        top: Val::Px(position.y),
+        width: Val::Px(PROGRESS_BAR_WIDTH),
+        height: Val::Px(5.),
+        ..default()
+    }
+}
+
+fn fill_style(progress: f32) -> Style {
+    Style {
+        width: Val::Percent(progress.clamp(0.0, 1.0) * 100.),
+        height: Val::Percent(100.),
+        ..default()
+    }
+}
+
+/// Only touches the style when it changed, so an unchanged marker doesn't cause a relayout.
+fn set_style(current: &mut Mut<Style>, style: Style) {
+    if **current != style {
+        **current = style;
+    }
+}
+
+fn hide(style: &mut Mut<Style>) {
+    if style.display != Display::None {
+        style.display = Display::None;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_queue_markers(
+    selected: SelectedWorkers,
+    working: Query<(), With<IsWorking>>,
+    transforms: Query<&GlobalTransform>,
+    targets: Query<(Option<&Health>, Option<&ConstructionSite>)>,
+    camera: Query<(&Camera, &GlobalTransform), With<Selector>>,
+    mut markers: Query<(&mut Text, &mut Style), (With<QueueMarker>, Without<ProgressBar>)>,
+    mut bars: Query<(&ProgressBar, &mut Style), Without<QueueMarker>>,
+    mut fills: Query<&mut Style, (Without<QueueMarker>, Without<ProgressBar>)>,
+    mut commands: Commands,
+) {
+    let mut labels = Vec::new();
+    let mut progress_bars = Vec::new();
+
+    if let Ok((camera, camera_transform)) = camera.get_single() {
+        for (entity, worker, queue, moving, _) in selected.iter() {
+            for (i, errand) in queue.iter().enumerate() {
+                if let Some(position) = target_position(errand, &transforms)
+                    .and_then(|t| camera.world_to_viewport(camera_transform, t))
+                {
+                    labels.push((position, (i + 1).to_string()));
+                }
+            }
+
+            if !working.contains(entity) {
+                continue;
+            }
+
+            let progress = queue
+                .iter()
+                .next()
+                .and_then(|e| progress(e, moving, &targets));
+            let position = camera
+                .world_to_viewport(camera_transform, worker.translation() + PROGRESS_BAR_OFFSET);
+            if let (Some(progress), Some(position)) = (progress, position) {
+                progress_bars.push((position, progress));
+            }
+        }
+    }
+
+    let mut markers = markers.iter_mut();
+    for (position, label) in labels {
+        let Some((mut text, mut style)) = markers.next() else {
+            commands.spawn((
+                TextBundle::from_section(
+                    label,
+                    TextStyle {
+                        font_size: 16.,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                )
+                .with_background_color(Color::BLACK.with_a(0.7))
+                .with_style(marker_style(position)),
+                QueueMarker,
+            ));
+            continue;
+        };
+
+        if text.sections[0].value != label {
+            text.sections[0].value = label;
+        }
+        set_style(&mut style, marker_style(position));
+    }
+    for (_, mut style) in markers {
+        hide(&mut style);
+    }
+
+    let mut bars = bars.iter_mut();
+    for (position, progress) in progress_bars {
+        let Some((bar, mut style)) = bars.next() else {
+            let fill = commands
+                .spawn(NodeBundle {
+                    style: fill_style(progress),
+                    background_color: PATH_COLOR.into(),
+                    ..default()
+                })
+                .id();
+            commands
+                .spawn((
+                    NodeBundle {
+                        style: bar_style(position),
+                        background_color: Color::BLACK.with_a(0.7).into(),
+                        ..default()
+                    },
+                    ProgressBar { fill },
+                ))
+                .add_child(fill);
+            continue;
+        };
+
+        set_style(&mut style, bar_style(position));
+        if let Ok(mut fill) = fills.get_mut(bar.fill) {
+            set_style(&mut fill, fill_style(progress));
+        }
+    }
+    for (_, mut style) in bars {
+        hide(&mut style);
+    }
+}
+
+fn spawn_queue_list(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_background_color(Color::BLACK.with_a(0.5))
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(45.0),
+            display: Display::None,
+            ..default()
+        }),
+        QueueList,
+    ));
+}
+
+fn update_queue_list(
+    selected: SelectedWorkers,
+    mut list: Query<(&mut Text, &mut Style), With<QueueList>>,
+) {
+    let mut value = String::new();
+
+    for (entity, _, queue, _, name) in selected.iter() {
+        match name {
+            Some(name) => value.push_str(name.as_str()),
+            None => value.push_str(&format!("{:?}", entity)),
+        }
+        value.push('\n');
+
+        if queue.len() == 0 {
+            value.push_str("  Idle\n");
+        }
+        for (i, errand) in queue.iter().enumerate() {
+            value.push_str(&format!("  {}. {}\n", i + 1, errand.name()));
+        }
+    }
+
+    for (mut text, mut style) in list.iter_mut() {
+        let display = if value.is_empty() {
+            Display::None
+        } else {
+            Display::Flex
+        };
+
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+        if style.display != display {
+            style.display = display;
+        }
+    }
+}
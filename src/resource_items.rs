@@ -0,0 +1,86 @@
+use crate::errands::Collectable;
+use crate::game_level::ResourceType;
+use crate::prelude::*;
+use crate::MyAssets;
+use bevy::prelude::shape::Icosphere;
+
+pub struct ResourceItemsPlugin;
+
+impl Plugin for ResourceItemsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Playing), create_resource_models);
+    }
+}
+
+/// A loose resource lying around in the world.
+#[derive(Component, Debug)]
+pub struct ResourceItem {
+    pub resource_type: ResourceType,
+}
+
+#[derive(Resource, Clone)]
+pub struct ResourceModels {
+    ore: Handle<Scene>,
+    crystal_mesh: Handle<Mesh>,
+    crystal_material: Handle<StandardMaterial>,
+}
+
+impl ResourceModels {
+    pub fn spawn(
+        &self,
+        commands: &mut Commands,
+        resource_type: ResourceType,
+        translation: Vec3,
+    ) -> Entity {
+        let mut item = match resource_type {
+            ResourceType::Ore => commands.spawn(SceneBundle {
+                transform: Transform::from_translation(translation),
+                scene: self.ore.clone(),
+                ..default()
+            }),
+            ResourceType::Crystal => commands.spawn(PbrBundle {
+                transform: Transform::from_translation(translation),
+                mesh: self.crystal_mesh.clone(),
+                material: self.crystal_material.clone(),
+                ..default()
+            }),
+        };
+
+        item.insert((
+            ResourceItem { resource_type },
+            Collectable,
+            Name::new(format!("{:?}", resource_type)),
+            RigidBody::Dynamic,
+            Collider::ball(0.5),
+        ));
+
+        item.id()
+    }
+}
+
+fn create_resource_models(
+    mut commands: Commands,
+    my_assets: Res<MyAssets>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let crystal_mesh = meshes.add(
+        Mesh::try_from(Icosphere {
+            radius: 0.5,
+            subdivisions: 0,
+        })
+        .expect("Failed to create crystal mesh"),
+    );
+
+    let crystal_material = materials.add(StandardMaterial {
+        base_color: ResourceType::Crystal.color(),
+        emissive: ResourceType::Crystal.color() * 0.5,
+        ..default()
+    });
+
+    commands.insert_resource(ResourceModels {
+        ore: my_assets.ore_model.clone(),
+        crystal_mesh,
+        crystal_material,
+    });
+}
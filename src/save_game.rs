@@ -0,0 +1,526 @@
+use crate::buildings::{
+    spawn_building, spawn_construction_site, BuildingFootprint, BuildingPlaceholder,
+    BuildingTypes, ConstructionMaterial, ConstructionSite, PlacingBuilding,
+};
+use crate::errands::carry_to_depot_errand::pick_up_item;
+use crate::errands::{Carrying, Designation, Minable, SavedErrand, SavedQueuedErrand};
+use crate::game_level::{GameLevel, ResourceType};
+use crate::game_level_render::{despawn_map_content, WorldTileTracker};
+use crate::grid::GridPosition;
+use crate::health::Health;
+use crate::prelude::*;
+use crate::resource_items::{ResourceItem, ResourceModels};
+use crate::stockpile::Stockpile;
+use crate::{spawn_raider, MyAssets};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+const QUICK_SAVE_PATH: &str = "saves/quicksave.json";
+
+pub struct SaveGamePlugin;
+
+impl Plugin for SaveGamePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                save_game.run_if(quick_save_pressed),
+                load_game.run_if(quick_load_pressed),
+                finish_loading
+                    .run_if(resource_exists::<PendingLoad>())
+                    .run_if(resource_exists::<ResourceModels>()),
+            )
+                .run_if(resource_exists::<GameLevel>())
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Refers to an entity in a way that survives saving and loading: walls by their tile, and
+/// everything else by its index in the save.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SavedEntity {
+    Wall(GridPosition),
+    Building(usize),
+    Item(usize),
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct SaveGame {
+    level: GameLevel,
+    stockpile: Vec<(ResourceType, u32)>,
+    world: SavedWorld,
+}
+
+/// Everything that can only be restored once the walls of the loaded level exist.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Default)]
+struct SavedWorld {
+    walls: Vec<SavedWall>,
+    buildings: Vec<SavedBuilding>,
+    items: Vec<SavedItem>,
+    raiders: Vec<SavedRaider>,
+    designations: Vec<SavedDesignation>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct SavedWall {
+    position: GridPosition,
+    health: f32,
+    max_health: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct SavedBuilding {
+    name: String,
+    translation: Vec3,
+    rotation: Quat,
+    tiles: Vec<GridPosition>,
+    state: SavedBuildingState,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case", tag = "type")]
+enum SavedBuildingState {
+    UnderConstruction {
+        delivered: Vec<ResourceType>,
+        progress: f32,
+    },
+    Finished {
+        health: f32,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct SavedItem {
+    resource_type: ResourceType,
+    translation: Vec3,
+    construction_site: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct SavedRaider {
+    name: String,
+    translation: Vec3,
+    rotation: Quat,
+    carrying: Option<SavedCarrying>,
+    errands: Vec<SavedQueuedErrand<SavedEntity>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct SavedCarrying {
+    item: usize,
+    discard_when_dropped: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct SavedDesignation {
+    target: SavedEntity,
+    errand: SavedErrand<SavedEntity>,
+}
+
+/// A loaded save waiting for the map of its level to be spawned.
+#[derive(Resource)]
+struct PendingLoad(SavedWorld);
+
+pub fn write_save_game(path: &Path, save: &SaveGame) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    std::fs::write(path, serde_json::to_string(save)?)?;
+
+    Ok(())
+}
+
+pub fn read_save_game(path: &Path) -> Result<SaveGame> {
+    let bytes = std::fs::read(path)?;
+
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+fn quick_save_pressed(control: Query<&ActionState<ControlAction>>) -> bool {
+    control
+        .iter()
+        .any(|a| a.just_pressed(ControlAction::QuickSave))
+}
+
+fn quick_load_pressed(control: Query<&ActionState<ControlAction>>) -> bool {
+    control
+        .iter()
+        .any(|a| a.just_pressed(ControlAction::QuickLoad))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn save_game(
+    level: Res<GameLevel>,
+    stockpile: Res<Stockpile>,
+    tracker: Res<WorldTileTracker>,
+    walls: Query<(Entity, &Health), With<Minable>>,
+    buildings: Query<(
+        Entity,
+        &Name,
+        &Transform,
+        &BuildingFootprint,
+        Option<&ConstructionSite>,
+        Option<&Health>,
+    )>,
+    items: Query<(
+        Entity,
+        &ResourceItem,
+        &GlobalTransform,
+        Option<&ConstructionMaterial>,
+    )>,
+    raiders: Query<(&Name, &Transform, &ErrandQueue, Option<&Carrying>)>,
+    designations: Query<(Entity, &Designation)>,
+) {
+    let mut references = HashMap::new();
+    let mut world = SavedWorld::default();
+
+    for (entity, health) in walls.iter() {
+        if let Some(position) = tracker.wall_position(entity) {
+            references.insert(entity, SavedEntity::Wall(position));
+            world.walls.push(SavedWall {
+                position,
+                health: health.current,
+                max_health: health.max,
+            });
+        }
+    }
+
+    for (entity, name, transform, footprint, site, health) in buildings.iter() {
+        let (name, state) = match (site, health) {
+            (Some(site), _) => (
+                site.building().get_name(),
+                SavedBuildingState::UnderConstruction {
+                    delivered: site.delivered().to_vec(),
+                    progress: site.progress(),
+                },
+            ),
+            (None, Some(health)) => (
+                name.to_string(),
+                SavedBuildingState::Finished {
+                    health: health.current,
+                },
+            ),
+            (None, None) => continue,
+        };
+
+        references.insert(entity, SavedEntity::Building(world.buildings.len()));
+        world.buildings.push(SavedBuilding {
+            name,
+            translation: transform.translation,
+            rotation: transform.rotation,
+            tiles: footprint.tiles.clone(),
+            state,
+        });
+    }
+
+    for (entity, item, transform, material) in items.iter() {
+        let construction_site = material.and_then(|m| match references.get(&m.site) {
+            Some(SavedEntity::Building(i)) => Some(*i),
+            _ => None,
+        });
+
+        references.insert(entity, SavedEntity::Item(world.items.len()));
+        world.items.push(SavedItem {
+            resource_type: item.resource_type,
+            translation: transform.translation(),
+            construction_site,
+        });
+    }
+
+    let to_saved = |entity: Entity| references.get(&entity).copied();
+
+    for (name, transform, queue, carrying) in raiders.iter() {
+        let carrying = carrying.and_then(|c| match to_saved(c.item) {
+            Some(SavedEntity::Item(item)) => Some(SavedCarrying {
+                item,
+                discard_when_dropped: c.discard_when_dropped,
+            }),
+            _ => None,
+        });
+
+        world.raiders.push(SavedRaider {
+            name: name.to_string(),
+            translation: transform.translation,
+            rotation: transform.rotation,
+            carrying,
+            errands: queue
+                .save()
+                .into_iter()
+                .filter_map(|e| e.try_map(to_saved))
+                .collect(),
+        });
+    }
+
+    for (entity, designation) in designations.iter() {
+        let saved = to_saved(entity).zip(designation.save().try_map(to_saved));
+        if let Some((target, errand)) = saved {
+            world.designations.push(SavedDesignation { target, errand });
+        }
+    }
+
+    let save = SaveGame {
+        level: GameLevel::clone(&level),
+        stockpile: ResourceType::ALL
+            .iter()
+            .map(|t| (*t, stockpile.get(*t)))
+            .collect(),
+        world,
+    };
+
+    match write_save_game(Path::new(QUICK_SAVE_PATH), &save) {
+        Ok(()) => info!("Saved game to {}", QUICK_SAVE_PATH),
+        Err(e) => error!("Failed to save game: {:?}", e),
+    }
+}
+
+/// Replaces the running game with the quick save. The level goes in right away, the rest
+/// waits in `PendingLoad` until the map is rebuilt.
+fn load_game(
+    old_world: Query<
+        Entity,
+        (
+            Or<(
+                With<ErrandQueue>,
+                With<BuildingFootprint>,
+                With<BuildingPlaceholder>,
+                With<ResourceItem>,
+            )>,
+            Without<Parent>,
+        ),
+    >,
+    mut stockpile: ResMut<Stockpile>,
+    mut commands: Commands,
+) {
+    let save = match read_save_game(Path::new(QUICK_SAVE_PATH)) {
+        Ok(save) => save,
+        Err(e) => {
+            error!("Failed to load game from {}: {:?}", QUICK_SAVE_PATH, e);
+            return;
+        }
+    };
+
+    for entity in old_world.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    despawn_map_content(&mut commands);
+    commands.remove_resource::<PlacingBuilding>();
+
+    for resource_type in ResourceType::ALL {
+        let amount = save
+            .stockpile
+            .iter()
+            .find(|(t, _)| *t == resource_type)
+            .map(|(_, amount)| *amount)
+            .unwrap_or(0);
+
+        stockpile.set(resource_type, amount);
+    }
+
+    commands.insert_resource(save.level);
+    commands.insert_resource(PendingLoad(save.world));
+
+    info!("Loaded game from {}", QUICK_SAVE_PATH);
+}
+
+fn finish_loading(
+    pending: Res<PendingLoad>,
+    tracker: Res<WorldTileTracker>,
+    building_types: Res<BuildingTypes>,
+    my_assets: Res<MyAssets>,
+    resource_models: Res<ResourceModels>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    if tracker.is_empty() {
+        return;
+    }
+
+    let world = &pending.0;
+    commands.remove_resource::<PendingLoad>();
+
+    for wall in &world.walls {
+        if let Some(entity) = tracker.wall_at(wall.position) {
+            commands.entity(entity).insert(Health {
+                current: wall.health,
+                max: wall.max_health,
+            });
+        }
+    }
+
+    let mut buildings = Vec::new();
+    for building in &world.buildings {
+        let Some(info) = building_types.get(&building.name) else {
+            warn!("Unknown building type {} in save", building.name);
+            buildings.push(None);
+            continue;
+        };
+
+        let transform =
+            Transform::from_translation(building.translation).with_rotation(building.rotation);
+
+        let entity = match &building.state {
+            SavedBuildingState::UnderConstruction {
+                delivered,
+                progress,
+            } => {
+                let site = spawn_construction_site(
+                    &mut commands,
+                    info.clone(),
+                    transform,
+                    building.tiles.clone(),
+                );
+                commands.entity(site).insert(ConstructionSite::restore(
+                    info,
+                    delivered.clone(),
+                    *progress,
+                ));
+
+                site
+            }
+            SavedBuildingState::Finished { health } => {
+                let entity =
+                    spawn_building(&mut commands, info.clone(), transform, building.tiles.clone());
+                commands.entity(entity).insert(Health {
+                    current: *health,
+                    max: info.get_build_time(),
+                });
+
+                entity
+            }
+        };
+        buildings.push(Some(entity));
+    }
+
+    let mut items = Vec::new();
+    for item in &world.items {
+        let entity = resource_models.spawn(&mut commands, item.resource_type, item.translation);
+
+        if let Some(site) = item.construction_site.and_then(|i| buildings[i]) {
+            commands.entity(entity).insert(ConstructionMaterial { site });
+        }
+        items.push(entity);
+    }
+
+    let resolve = |saved: SavedEntity| match saved {
+        SavedEntity::Wall(position) => tracker.wall_at(position),
+        SavedEntity::Building(i) => buildings.get(i).copied().flatten(),
+        SavedEntity::Item(i) => items.get(i).copied(),
+    };
+
+    let designations: HashMap<_, _> = world
+        .designations
+        .iter()
+        .filter_map(|d| {
+            let target = resolve(d.target)?;
+            let errand = d.errand.clone().try_map(resolve)?;
+
+            Some((target, errand.designate(target)))
+        })
+        .collect();
+
+    for raider in &world.raiders {
+        let entity = spawn_raider(
+            &mut commands,
+            &my_assets,
+            raider.name.clone(),
+            Transform::from_translation(raider.translation).with_rotation(raider.rotation),
+        );
+
+        if let Some(carrying) = &raider.carrying {
+            if let Some(item) = items.get(carrying.item) {
+                pick_up_item(&mut commands, entity, *item, carrying.discard_when_dropped);
+            }
+        }
+
+        let mut queue = ErrandQueue::new();
+        for errand in raider.errands.iter().cloned() {
+            let Some(errand) = errand.try_map(resolve) else {
+                continue;
+            };
+
+            let reserved = errand
+                .designation
+                .and_then(|d| designations.get(&d))
+                .is_some_and(|d| d.enqueue(entity, &mut queue, time.elapsed_seconds()));
+
+            if !reserved {
+                errand.errand.append_to(&mut queue, &errand.fail_if_missing);
+            }
+        }
+        commands.entity(entity).insert(queue);
+    }
+
+    for (entity, designation) in designations {
+        commands.entity(entity).insert(designation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+
+    #[test]
+    fn save_survives_json_round_trip() {
+        let mut carved = Grid::new(4, 3, false);
+        carved.set(1, 1, true);
+        carved.set(2, 1, true);
+
+        let save = SaveGame {
+            level: GameLevel::new_from_carved_tiles(carved, &[GridPosition::new(1, 1)]),
+            stockpile: vec![(ResourceType::Ore, 3), (ResourceType::Crystal, 0)],
+            world: SavedWorld {
+                walls: vec![SavedWall {
+                    position: GridPosition::new(3, 1),
+                    health: 1.5,
+                    max_health: 3.0,
+                }],
+                buildings: vec![SavedBuilding {
+                    name: "Depot".to_string(),
+                    translation: Vec3::new(15.0, 0.0, 15.0),
+                    rotation: Quat::IDENTITY,
+                    tiles: vec![GridPosition::new(1, 1)],
+                    state: SavedBuildingState::UnderConstruction {
+                        delivered: vec![ResourceType::Ore],
+                        progress: 0.0,
+                    },
+                }],
+                items: vec![SavedItem {
+                    resource_type: ResourceType::Ore,
+                    translation: Vec3::new(25.0, 1.0, 15.0),
+                    construction_site: Some(0),
+                }],
+                raiders: vec![SavedRaider {
+                    name: "Raider0".to_string(),
+                    translation: Vec3::new(25.0, 3.2, 15.0),
+                    rotation: Quat::IDENTITY,
+                    carrying: Some(SavedCarrying {
+                        item: 0,
+                        discard_when_dropped: true,
+                    }),
+                    errands: vec![SavedQueuedErrand {
+                        errand: SavedErrand::Build {
+                            site: SavedEntity::Building(0),
+                        },
+                        designation: Some(SavedEntity::Building(0)),
+                        fail_if_missing: vec![SavedEntity::Building(0)],
+                    }],
+                }],
+                designations: vec![SavedDesignation {
+                    target: SavedEntity::Wall(GridPosition::new(3, 1)),
+                    errand: SavedErrand::MineWall {
+                        target: SavedEntity::Wall(GridPosition::new(3, 1)),
+                    },
+                }],
+            },
+        };
+
+        let json = serde_json::to_string(&save).unwrap();
+
+        assert_eq!(serde_json::from_str::<SaveGame>(&json).unwrap(), save);
+    }
+}
@@ -1,4 +1,5 @@
 use bevy::pbr::{NotShadowCaster, NotShadowReceiver};
+use crate::camera_control::Selector;
 use crate::prelude::*;
 use bevy::prelude::shape::Torus;
 use bevy::render::primitives::Aabb;
@@ -23,12 +24,115 @@ pub struct SelectionPlugin;
 impl Plugin for SelectionPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<WantToSelect>()
-            .add_systems(Update, (apply_select, highlight_selected))
-            .add_systems(PostUpdate, unhighlight_deselected)
+            .init_resource::<ControlGroups>()
+            .add_systems(Update, (use_control_groups, apply_select, highlight_selected).chain())
+            .add_systems(PostUpdate, (unhighlight_deselected, drop_removed_from_control_groups))
             .add_systems(Startup, add_glow_highlight_material);
     }
 }
 
+const DOUBLE_TAP_SECONDS: f32 = 0.3;
+
+/// Selections bound to the number keys.
+#[derive(Resource, Default)]
+pub struct ControlGroups {
+    groups: [Vec<Entity>; 9],
+    last_recall: Option<(usize, f32)>,
+}
+
+impl ControlGroups {
+    pub fn bind(&mut self, group: usize, entities: Vec<Entity>) {
+        self.groups[group] = entities;
+    }
+
+    pub fn get(&self, group: usize) -> &[Entity] {
+        &self.groups[group]
+    }
+
+    fn remove(&mut self, entity: Entity) {
+        for group in self.groups.iter_mut() {
+            group.retain(|e| *e != entity);
+        }
+    }
+
+    /// Returns whether the group was recalled twice in quick succession.
+    fn recall(&mut self, group: usize, now: f32) -> bool {
+        let double_tap = self
+            .last_recall
+            .is_some_and(|(last, at)| last == group && now - at <= DOUBLE_TAP_SECONDS);
+
+        self.last_recall = if double_tap { None } else { Some((group, now)) };
+
+        double_tap
+    }
+}
+
+/// Moves the camera sideways until it looks at `target`, keeping its height and angle.
+fn centered_on(camera: &Transform, target: Vec3) -> Vec3 {
+    let forward = camera.forward();
+
+    if forward.y > -f32::EPSILON {
+        return Vec3::new(target.x, camera.translation.y, target.z);
+    }
+
+    let distance = (camera.translation.y - target.y) / -forward.y;
+    target - forward * distance
+}
+
+fn use_control_groups(
+    mut camera: Query<(&ActionState<ControlAction>, &mut Transform), With<Selector>>,
+    selected: Query<Entity, With<Selected>>,
+    positions: Query<&GlobalTransform>,
+    mut groups: ResMut<ControlGroups>,
+    mut events: EventWriter<WantToSelect>,
+    time: Res<Time>,
+) {
+    for (action_state, mut transform) in camera.iter_mut() {
+        for (group, action) in ControlAction::CONTROL_GROUPS.into_iter().enumerate() {
+            if !action_state.just_pressed(action) {
+                continue;
+            }
+
+            if action_state.pressed(ControlAction::BindControlGroup) {
+                info!("Binding control group {}", group + 1);
+                groups.bind(group, selected.iter().collect());
+                continue;
+            }
+
+            let members = groups.get(group).to_vec();
+            if members.is_empty() {
+                continue;
+            }
+
+            events.send(WantToSelect::Clear);
+            events.send_batch(members.iter().map(|e| WantToSelect::Additionally(*e)));
+
+            if groups.recall(group, time.elapsed_seconds()) {
+                let positions = members
+                    .iter()
+                    .filter_map(|e| positions.get(*e).ok())
+                    .map(|t| t.translation())
+                    .collect_vec();
+
+                if !positions.is_empty() {
+                    let center = positions.iter().sum::<Vec3>() / positions.len() as f32;
+                    transform.translation = centered_on(&transform, center);
+                }
+            }
+        }
+    }
+}
+
+/// Despawned entities are removed from every component, so this also catches them.
+fn drop_removed_from_control_groups(
+    mut removed: RemovedComponents<Selectable>,
+    mut groups: ResMut<ControlGroups>,
+) {
+    for entity in removed.iter() {
+        groups.remove(entity);
+    }
+}
+
 fn apply_select(
     mut commands: Commands,
     query: Query<Entity, With<Selected>>,
@@ -151,3 +255,85 @@ fn unhighlight_deselected(
         }
     }
 }
+
+/// What a box over `rect` selects out of `candidates`, given as their screen position and
+/// whether they are units. Walls, floors and buildings are only selected along with the units
+/// when `include_everything` is set, so dragging over the base just picks up the raiders.
+pub fn box_selection(
+    rect: Rect,
+    candidates: impl IntoIterator<Item = (Entity, Vec2, bool)>,
+    include_everything: bool,
+) -> Vec<Entity> {
+    candidates
+        .into_iter()
+        .filter(|(_, position, is_unit)| {
+            (*is_unit || include_everything) && rect.contains(*position)
+        })
+        .map(|(entity, ..)| entity)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn box_selection_only_picks_up_units_unless_asked_to() {
+        let rect = Rect::new(0.0, 0.0, 100.0, 100.0);
+        let unit = Entity::from_raw(1);
+        let building = Entity::from_raw(2);
+        let wall = Entity::from_raw(3);
+        let outside = Entity::from_raw(4);
+        let candidates = [
+            (unit, Vec2::new(10.0, 10.0), true),
+            (building, Vec2::new(50.0, 50.0), false),
+            (wall, Vec2::new(90.0, 20.0), false),
+            (outside, Vec2::new(150.0, 10.0), true),
+        ];
+
+        assert_eq!(box_selection(rect, candidates, false), vec![unit]);
+        assert_eq!(
+            box_selection(rect, candidates, true),
+            vec![unit, building, wall]
+        );
+    }
+
+    #[test]
+    fn only_quick_repeated_recalls_are_double_taps() {
+        let mut groups = ControlGroups::default();
+
+        assert!(!groups.recall(0, 1.0));
+        assert!(groups.recall(0, 1.2));
+        assert!(!groups.recall(0, 1.3), "a double tap shouldn't start the next one");
+        assert!(!groups.recall(1, 1.4));
+        assert!(!groups.recall(1, 2.0));
+    }
+
+    #[test]
+    fn removed_entities_drop_out_of_every_group() {
+        let mut groups = ControlGroups::default();
+        let (a, b) = (Entity::from_raw(1), Entity::from_raw(2));
+        groups.bind(0, vec![a, b]);
+        groups.bind(4, vec![a]);
+
+        groups.remove(a);
+
+        assert_eq!(groups.get(0), &[b]);
+        assert!(groups.get(4).is_empty());
+    }
+
+    #[test]
+    fn centered_camera_looks_at_the_target() {
+        let camera = Transform::from_xyz(15.0, 20.0, 15.0).looking_at(Vec3::ZERO, Vec3::Y);
+        let target = Vec3::new(100.0, 3.0, -40.0);
+
+        let centered = Transform {
+            translation: centered_on(&camera, target),
+            ..camera
+        };
+
+        assert_eq!(centered.translation.y, camera.translation.y);
+        let distance = centered.translation.distance(target);
+        assert!((centered.translation + centered.forward() * distance).distance(target) < 0.001);
+    }
+}
@@ -0,0 +1,224 @@
+use crate::game_level::ResourceType;
+use crate::prelude::*;
+use std::collections::HashMap;
+
+pub struct StockpilePlugin;
+
+impl Plugin for StockpilePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Stockpile>()
+            .add_event::<StockpileChanged>()
+            .add_systems(Startup, spawn_stockpile_text)
+            .add_systems(
+                Update,
+                (
+                    send_stockpile_changes.run_if(resource_changed::<Stockpile>()),
+                    update_stockpile_text,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Resources delivered to the depots, shared by the whole base.
+#[derive(Resource, Default, Debug)]
+pub struct Stockpile {
+    amounts: HashMap<ResourceType, u32>,
+    reported: HashMap<ResourceType, u32>,
+}
+
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct StockpileChanged {
+    pub resource_type: ResourceType,
+    pub previous: u32,
+    pub current: u32,
+}
+
+impl Stockpile {
+    pub fn get(&self, resource_type: ResourceType) -> u32 {
+        self.amounts.get(&resource_type).copied().unwrap_or(0)
+    }
+
+    pub fn can_afford(&self, cost: &[(ResourceType, u32)]) -> bool {
+        ResourceType::ALL.iter().all(|resource_type| {
+            let needed: u32 = cost
+                .iter()
+                .filter(|(t, _)| t == resource_type)
+                .map(|(_, amount)| amount)
+                .sum();
+
+            self.get(*resource_type) >= needed
+        })
+    }
+
+    pub fn set(&mut self, resource_type: ResourceType, amount: u32) {
+        self.amounts.insert(resource_type, amount);
+    }
+
+    pub fn add(&mut self, resource_type: ResourceType, amount: u32) {
+        *self.amounts.entry(resource_type).or_default() += amount;
+    }
+
+    /// Removes the whole cost from the stockpile, or nothing at all if it can't be afforded.
+    pub fn try_spend(&mut self, cost: &[(ResourceType, u32)]) -> bool {
+        if !self.can_afford(cost) {
+            return false;
+        }
+
+        for (resource_type, amount) in cost {
+            *self.amounts.entry(*resource_type).or_default() -= amount;
+        }
+
+        true
+    }
+
+    pub fn refund(&mut self, cost: &[(ResourceType, u32)]) {
+        for (resource_type, amount) in cost {
+            self.add(*resource_type, *amount);
+        }
+    }
+
+    fn drain_changes(&mut self) -> Vec<StockpileChanged> {
+        let mut changes = Vec::new();
+
+        for resource_type in ResourceType::ALL {
+            let previous = self.reported.get(&resource_type).copied().unwrap_or(0);
+            let current = self.get(resource_type);
+
+            if previous != current {
+                self.reported.insert(resource_type, current);
+                changes.push(StockpileChanged {
+                    resource_type,
+                    previous,
+                    current,
+                });
+            }
+        }
+
+        changes
+    }
+}
+
+fn send_stockpile_changes(
+    mut stockpile: ResMut<Stockpile>,
+    mut events: EventWriter<StockpileChanged>,
+) {
+    events.send_batch(stockpile.bypass_change_detection().drain_changes());
+}
+
+#[derive(Component)]
+struct StockpileText;
+
+fn spawn_stockpile_text(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let fira_sans_bold = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let fira_mono_medium = asset_server.load("fonts/FiraMono-Medium.ttf");
+
+    let sections = ResourceType::ALL.iter().flat_map(|resource_type| {
+        [
+            TextSection::new(
+                format!(" {:?}: ", resource_type),
+                TextStyle {
+                    font: fira_sans_bold.clone(),
+                    font_size: 16.0,
+                    color: resource_type.color(),
+                },
+            ),
+            TextSection::new(
+                "0",
+                TextStyle {
+                    font: fira_mono_medium.clone(),
+                    font_size: 16.0,
+                    color: Color::WHITE,
+                },
+            ),
+        ]
+    });
+
+    commands.spawn((
+        TextBundle::from_sections(sections)
+            .with_background_color(Color::BLACK.with_a(0.5))
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(120.0),
+                ..default()
+            }),
+        StockpileText,
+    ));
+}
+
+fn update_stockpile_text(
+    mut events: EventReader<StockpileChanged>,
+    mut query: Query<&mut Text, With<StockpileText>>,
+) {
+    for event in events.iter() {
+        let index = ResourceType::ALL
+            .iter()
+            .position(|t| *t == event.resource_type)
+            .unwrap();
+
+        for mut text in query.iter_mut() {
+            text.sections[index * 2 + 1].value = event.current.to_string();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spending_is_all_or_nothing() {
+        let mut stockpile = Stockpile::default();
+        stockpile.add(ResourceType::Ore, 5);
+        stockpile.add(ResourceType::Crystal, 1);
+
+        assert!(!stockpile.try_spend(&[(ResourceType::Ore, 3), (ResourceType::Crystal, 2)]));
+        assert_eq!(stockpile.get(ResourceType::Ore), 5);
+        assert_eq!(stockpile.get(ResourceType::Crystal), 1);
+
+        assert!(stockpile.try_spend(&[(ResourceType::Ore, 3), (ResourceType::Crystal, 1)]));
+        assert_eq!(stockpile.get(ResourceType::Ore), 2);
+        assert_eq!(stockpile.get(ResourceType::Crystal), 0);
+    }
+
+    #[test]
+    fn repeated_resources_in_cost_are_summed() {
+        let mut stockpile = Stockpile::default();
+        stockpile.add(ResourceType::Ore, 3);
+
+        assert!(!stockpile.can_afford(&[(ResourceType::Ore, 2), (ResourceType::Ore, 2)]));
+        assert!(stockpile.can_afford(&[(ResourceType::Ore, 2), (ResourceType::Ore, 1)]));
+    }
+
+    #[test]
+    fn refund_restores_spent_resources() {
+        let mut stockpile = Stockpile::default();
+        stockpile.add(ResourceType::Ore, 4);
+
+        let cost = [(ResourceType::Ore, 4)];
+        assert!(stockpile.try_spend(&cost));
+        stockpile.refund(&cost);
+
+        assert_eq!(stockpile.get(ResourceType::Ore), 4);
+    }
+
+    #[test]
+    fn reports_only_changed_amounts() {
+        let mut stockpile = Stockpile::default();
+        stockpile.add(ResourceType::Crystal, 2);
+
+        assert_eq!(
+            stockpile.drain_changes(),
+            vec![StockpileChanged {
+                resource_type: ResourceType::Crystal,
+                previous: 0,
+                current: 2,
+            }]
+        );
+
+        stockpile.add(ResourceType::Ore, 1);
+        stockpile.try_spend(&[(ResourceType::Ore, 1)]);
+
+        assert!(stockpile.drain_changes().is_empty());
+    }
+}
@@ -0,0 +1,29 @@
+//! What every test app has in common: time moves on by the same fixed step every update, and
+//! tests step through a number of updates at once.
+
+use crate::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use std::time::Duration;
+
+/// Every update advances the game by exactly this many seconds.
+pub const TEST_TIMESTEP: f32 = 1.0 / 60.0;
+
+pub trait TestAppExtensions {
+    /// Makes every update advance time by [`TEST_TIMESTEP`].
+    fn use_test_timestep(&mut self) -> &mut Self;
+    fn step(&mut self, frames: usize);
+}
+
+impl TestAppExtensions for App {
+    fn use_test_timestep(&mut self) -> &mut Self {
+        self.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            TEST_TIMESTEP,
+        )))
+    }
+
+    fn step(&mut self, frames: usize) {
+        for _ in 0..frames {
+            self.update();
+        }
+    }
+}